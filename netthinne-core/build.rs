@@ -0,0 +1,208 @@
+use burn_onnx::ModelGen;
+use std::{collections::HashMap, env, fs, io::Write, path::Path};
+
+/// ImageNet-1k classes scored by MobileNetV2.
+const MOBILENET_CLASSES: usize = 1000;
+/// COCO classes scored by YOLOv8.
+const YOLO_CLASSES: usize = 80;
+
+fn main() {
+    ModelGen::new()
+        .input("src/ml/mobilenet_v2_1_4_fp32.onnx")
+        .out_dir("ml/")
+        .embed_states(true)
+        .run_from_script();
+
+    ModelGen::new()
+        .input("src/ml/yolov8n.onnx")
+        .out_dir("ml/")
+        .embed_states(true)
+        .run_from_script();
+
+    fix_padding_compat("ml/mobilenet_v2_1_4_fp32.rs");
+    fix_padding_compat("ml/yolov8n.rs");
+
+    check_counts(
+        "MobileNetV2",
+        MOBILENET_CLASSES,
+        &[
+            generate_labels("src/ml/labels_in1k.txt", "ml/labels_in1k.rs", "LABELS_IN1K"),
+            generate_labels(
+                "src/ml/labels_in1k_norsk.tsv",
+                "ml/labels_in1k_norsk.rs",
+                "LABELS_IN1K_NORSK",
+            ),
+        ],
+    );
+    check_counts(
+        "YOLOv8",
+        YOLO_CLASSES,
+        &[
+            generate_labels("src/ml/labels_yolo.txt", "ml/labels_yolo.rs", "LABELS_YOLO"),
+            generate_labels(
+                "src/ml/labels_yolo_norsk.tsv",
+                "ml/labels_yolo_norsk.rs",
+                "LABELS_YOLO_NORSK",
+            ),
+        ],
+    );
+}
+
+/// burn-onnx 0.21 emits PaddingConfig2d::Explicit(top, left, bottom, right) but
+/// burn-nn 0.20 expects Explicit(height, width). Rewrite 4-arg calls to 2-arg.
+fn fix_padding_compat(generated_file: &str) {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let path = Path::new(&out_dir).join(generated_file);
+    if !path.exists() {
+        return;
+    }
+    let src = fs::read_to_string(&path).unwrap();
+    let fixed = rewrite_explicit_padding(&src)
+        .replace("shape1_out1[actual_idx] as i64", "shape1_out1[actual_idx]");
+    fs::write(&path, fixed).unwrap();
+}
+
+fn rewrite_explicit_padding(src: &str) -> String {
+    let marker = "PaddingConfig2d::Explicit(";
+    let mut out = String::with_capacity(src.len());
+    let mut rest = src;
+    while let Some(pos) = rest.find(marker) {
+        out.push_str(&rest[..pos + marker.len()]);
+        rest = &rest[pos + marker.len()..];
+        if let Some(end) = rest.find(')') {
+            let args: Vec<&str> = rest[..end].split(", ").collect();
+            if args.len() == 4 {
+                let top: i64 = args[0].trim().parse().unwrap_or(0);
+                let left: i64 = args[1].trim().parse().unwrap_or(0);
+                let bottom: i64 = args[2].trim().parse().unwrap_or(0);
+                let right: i64 = args[3].trim().parse().unwrap_or(0);
+                let h = (top + bottom + 1) / 2;
+                let w = (left + right + 1) / 2;
+                out.push_str(&h.to_string());
+                out.push_str(", ");
+                out.push_str(&w.to_string());
+            } else {
+                out.push_str(&rest[..end]);
+            }
+            out.push(')');
+            rest = &rest[end + 1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Emits a Rust static array in OUT_DIR from a label file: one `&str` per line
+/// for `.txt`, or one `Label` per row for the Norwegian `.tsv` tables.
+/// Returns the path and number of labels for `check_counts`.
+fn generate_labels<'a>(src_path: &'a str, out_file: &str, const_name: &str) -> (&'a str, usize) {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let out_path = Path::new(&out_dir).join(out_file);
+
+    let text = fs::read_to_string(src_path).unwrap_or_else(|_| panic!("{src_path} not found"));
+
+    let (ty, entries) = if src_path.ends_with(".tsv") {
+        ("Label", label_rows(src_path, &text))
+    } else {
+        let mut seen = HashMap::new();
+        let entries = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let label = line.trim();
+                check_entry(src_path, i + 1, label, &mut seen);
+                format!("    {}", literal(label))
+            })
+            .collect();
+        ("&str", entries)
+    };
+
+    let mut f = fs::File::create(&out_path).unwrap();
+    writeln!(f, "pub static {const_name}: &[{ty}] = &[").unwrap();
+    for entry in &entries {
+        writeln!(f, "{entry},").unwrap();
+    }
+    writeln!(f, "];").unwrap();
+
+    println!("cargo:rerun-if-changed={src_path}");
+    (src_path, entries.len())
+}
+
+/// Turns `noun, gender, definite, plural, definite plural` rows into `Label`
+/// literals. `#` lines are comments and `-` marks a missing plural form.
+fn label_rows(src_path: &str, text: &str) -> Vec<String> {
+    let optional = |form: &str| match form {
+        "-" => "None".to_string(),
+        form => format!("Some({})", literal(form)),
+    };
+    let mut seen = HashMap::new();
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.starts_with('#'))
+        .map(|(i, line)| {
+            let cols: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let [noun, gender, definite, plural, definite_plural] = cols[..] else {
+                panic!("{src_path}:{}: expected 5 tab-separated columns", i + 1);
+            };
+            check_entry(src_path, i + 1, noun, &mut seen);
+            if let Some(column) = [gender, definite, plural, definite_plural]
+                .iter()
+                .position(|form| form.is_empty())
+            {
+                panic!("{src_path}:{}: column {} is empty", i + 1, column + 2);
+            }
+            let gender = match gender {
+                "m" => "Masculine",
+                "f" => "Feminine",
+                "n" => "Neuter",
+                other => panic!("{src_path}:{}: unknown gender {other:?}", i + 1),
+            };
+            format!(
+                "    Label {{ noun: {}, gender: Gender::{gender}, definite: {}, \
+                 plural: {}, definite_plural: {} }}",
+                literal(noun),
+                literal(definite),
+                optional(plural),
+                optional(definite_plural)
+            )
+        })
+        .collect()
+}
+
+/// Rejects empty labels and labels already seen on an earlier line of the file,
+/// since two classes with one name cannot be told apart in the app.
+fn check_entry<'a>(
+    src_path: &str,
+    line: usize,
+    label: &'a str,
+    seen: &mut HashMap<&'a str, usize>,
+) {
+    if label.is_empty() {
+        panic!("{src_path}:{line}: empty label");
+    }
+    if let Some(first) = seen.insert(label, line) {
+        panic!("{src_path}:{line}: duplicate label {label:?}, first on line {first}");
+    }
+}
+
+/// Panics unless every label file of a model has one entry per output class;
+/// otherwise class indices would silently shift between the model and a table.
+fn check_counts(model: &str, classes: usize, files: &[(&str, usize)]) {
+    for &(src_path, count) in files {
+        if count != classes {
+            let counts: Vec<String> = files
+                .iter()
+                .map(|(path, count)| format!("{path}: {count}"))
+                .collect();
+            panic!(
+                "{src_path} has {count} labels but {model} outputs {classes} classes ({})",
+                counts.join(", ")
+            );
+        }
+    }
+}
+
+/// Rust string literal for `s`; `Debug` escapes quotes, backslashes and control characters.
+fn literal(s: &str) -> String {
+    format!("{s:?}")
+}
@@ -0,0 +1,326 @@
+//! Batch labeling of image files with the same two-stage pipeline as the web app.
+//!
+//! Prints one JSON entry per image with the English and Norwegian labels of
+//! every detected object. Built only with `--features cli`.
+
+use std::fmt::Display;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use netthinne_core::ml::{
+    self, DetectedObject, DetectionConfig, Prediction, Suppression, YoloLabel,
+};
+use netthinne_core::{image_utils, Error};
+
+/// Same limit the app applies to stills, so results match a scan in the browser.
+const MAX_SIDE: u32 = 2048;
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+
+const USAGE: &str = "\
+Usage: netthinne-cli [OPTIONS] <PATH>...
+
+Labels images in English and Norwegian and prints the results as JSON.
+A directory PATH labels every JPEG, PNG and WebP file directly inside it.
+
+Options:
+  -o, --output <FILE>         Write the JSON to FILE instead of stdout
+      --conf <SCORE>          Minimum detection score, 0.05-0.95 [default: 0.25]
+      --iou <OVERLAP>         Overlap above which boxes are merged, 0.05-0.95 [default: 0.45]
+      --max-detections <N>    Most objects labeled per image, 1-20 [default: 3]
+      --suppression <MODE>    per-class, class-agnostic or soft [default: per-class]
+  -h, --help                  Print this help";
+
+#[derive(Debug)]
+struct Args {
+    paths: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    config: DetectionConfig,
+}
+
+/// Result for one input file, either its objects or why it failed.
+#[derive(Serialize)]
+struct ImageResult {
+    path: PathBuf,
+    #[serde(flatten)]
+    outcome: Outcome,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Outcome {
+    Objects(Vec<Object>),
+    Error(String),
+}
+
+/// `DetectedObject` without the crop preview, which is only useful on screen.
+#[derive(Serialize)]
+struct Object {
+    /// Box in source image pixels as [x1, y1, x2, y2].
+    bbox: [f32; 4],
+    yolo: Option<YoloLabel>,
+    inet_predictions: Vec<Prediction>,
+}
+
+impl From<DetectedObject> for Object {
+    fn from(object: DetectedObject) -> Self {
+        Object {
+            bbox: object.bbox,
+            yolo: object.yolo,
+            inet_predictions: object.inet_predictions,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let files = match collect_images(&args.paths) {
+        Ok(files) => files,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let total = files.len();
+    let results: Vec<ImageResult> = files
+        .into_iter()
+        .enumerate()
+        .map(|(i, path)| {
+            eprintln!("[{}/{total}] {}", i + 1, path.display());
+            let outcome = match label_image(&path, &args.config) {
+                Ok(objects) => Outcome::Objects(objects),
+                Err(e) => {
+                    eprintln!("  {e}");
+                    Outcome::Error(e.to_string())
+                }
+            };
+            ImageResult { path, outcome }
+        })
+        .collect();
+    let failed = results
+        .iter()
+        .any(|r| matches!(r.outcome, Outcome::Error(_)));
+
+    let json = serde_json::to_string_pretty(&results).expect("results serialize to JSON");
+    match &args.output {
+        Some(path) => {
+            if let Err(e) = fs::write(path, json + "\n") {
+                eprintln!("error: could not write {}: {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        }
+        None => println!("{json}"),
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// `Ok(None)` when help was requested.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut parsed = Args {
+        paths: Vec::new(),
+        output: None,
+        config: DetectionConfig::default(),
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name} needs a value"));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-o" | "--output" => parsed.output = Some(value(&arg)?.into()),
+            "--conf" => {
+                parsed.config.conf_threshold =
+                    parse_in_range(&arg, value(&arg)?, DetectionConfig::THRESHOLD_RANGE)?
+            }
+            "--iou" => {
+                parsed.config.iou_threshold =
+                    parse_in_range(&arg, value(&arg)?, DetectionConfig::THRESHOLD_RANGE)?
+            }
+            "--max-detections" => {
+                parsed.config.max_detections =
+                    parse_in_range(&arg, value(&arg)?, DetectionConfig::MAX_DETECTIONS_RANGE)?
+            }
+            "--suppression" => parsed.config.suppression = value(&arg)?.parse::<Suppression>()?,
+            flag if flag.starts_with('-') => return Err(format!("unknown option {flag}")),
+            path => parsed.paths.push(path.into()),
+        }
+    }
+
+    if parsed.paths.is_empty() {
+        return Err("no images given".into());
+    }
+    Ok(Some(parsed))
+}
+
+/// Parses `value` and rejects it outside `range`, the same range the app's
+/// settings screen offers. NaN is never in range.
+fn parse_in_range<T>(name: &str, value: String, range: RangeInclusive<T>) -> Result<T, String>
+where
+    T: std::str::FromStr + PartialOrd + Display,
+{
+    let invalid = || {
+        format!(
+            "invalid value for {name}: {value} (expected {} to {})",
+            range.start(),
+            range.end()
+        )
+    };
+    let parsed: T = value.parse().map_err(|_| invalid())?;
+    if range.contains(&parsed) {
+        Ok(parsed)
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Expands directories into the images directly inside them, sorted by name.
+/// Files named explicitly are kept whatever their extension.
+fn collect_images(paths: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let entries =
+            fs::read_dir(path).map_err(|e| format!("could not read {}: {e}", path.display()))?;
+        let mut images: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| file.is_file() && is_image(file))
+            .collect();
+        images.sort();
+        files.extend(images);
+    }
+    Ok(files)
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            IMAGE_EXTENSIONS
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        })
+}
+
+fn label_image(path: &Path, config: &DetectionConfig) -> Result<Vec<Object>, Error> {
+    let bytes =
+        fs::read(path).map_err(|e| Error::Preprocessing(format!("Could not read file: {e}")))?;
+    let frame = image_utils::fit_within(image_utils::decode_image(&bytes)?, MAX_SIDE)?;
+    let objects = ml::process_image(&frame.pixels, frame.width, frame.height, config, |_| {})?;
+    Ok(objects.into_iter().map(Object::from).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Args>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_args_reads_options_and_paths() {
+        let args = parse(&[
+            "photos",
+            "-o",
+            "out.json",
+            "--conf",
+            "0.5",
+            "--iou",
+            "0.3",
+            "--max-detections",
+            "7",
+            "--suppression",
+            "soft",
+            "cat.jpg",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            args.paths,
+            [PathBuf::from("photos"), PathBuf::from("cat.jpg")]
+        );
+        assert_eq!(args.output, Some(PathBuf::from("out.json")));
+        assert_eq!(
+            args.config,
+            DetectionConfig {
+                conf_threshold: 0.5,
+                iou_threshold: 0.3,
+                max_detections: 7,
+                suppression: Suppression::Soft,
+            }
+        );
+
+        let defaults = parse(&["cat.jpg"]).unwrap().unwrap();
+        assert_eq!(defaults.config, DetectionConfig::default());
+        assert!(parse(&["cat.jpg", "--help"]).unwrap().is_none());
+    }
+
+    #[test]
+    fn parse_args_rejects_bad_input() {
+        for args in [
+            &[][..],
+            &["--conf", "0.5"],
+            &["cat.jpg", "--conf"],
+            &["cat.jpg", "--conf", "nan"],
+            &["cat.jpg", "--conf", "1.5"],
+            &["cat.jpg", "--iou", "0"],
+            &["cat.jpg", "--max-detections", "0"],
+            &["cat.jpg", "--max-detections", "-1"],
+            &["cat.jpg", "--suppression", "greedy"],
+            &["cat.jpg", "--verbose"],
+        ] {
+            assert!(parse(args).is_err(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn collect_images_expands_directories() {
+        let dir = std::env::temp_dir().join(format!("netthinne-cli-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        for name in ["b.PNG", "a.jpg", "c.webp", "notes.txt", "nested/d.jpg"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        let explicit = dir.join("notes.txt");
+
+        let files = collect_images(&[dir.clone(), explicit.clone()]).unwrap();
+        let missing = collect_images(&[dir.join("missing")]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            files,
+            [
+                dir.join("a.jpg"),
+                dir.join("b.PNG"),
+                dir.join("c.webp"),
+                explicit
+            ]
+        );
+        // A missing path is passed through and reported when it is read.
+        assert_eq!(missing, [dir.join("missing")]);
+    }
+}
@@ -0,0 +1,23 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Everything that can go wrong between decoding an image and labeling it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Error {
+    /// Converting, cropping or encoding pixels failed.
+    Preprocessing(String),
+    /// A model produced output that could not be read.
+    Inference(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Preprocessing(detail) => write!(f, "Image processing failed: {detail}"),
+            Error::Inference(detail) => write!(f, "Recognition failed: {detail}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
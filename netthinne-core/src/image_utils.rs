@@ -0,0 +1,527 @@
+use std::io::Cursor;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{
+    codecs::{jpeg::JpegEncoder, png::PngEncoder},
+    imageops,
+    metadata::Orientation,
+    DynamicImage, ImageBuffer, ImageDecoder, ImageEncoder, ImageReader, Pixel, RgbImage, RgbaImage,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+const MOBILENET_SIZE: usize = 224;
+const MOBILENET_MEAN: f32 = 0.5;
+const MOBILENET_STD: f32 = 0.5;
+const YOLO_SIZE: usize = 640;
+/// Gray used by Ultralytics for letterbox padding.
+const LETTERBOX_FILL: u8 = 114;
+const THUMBNAIL_QUALITY: u8 = 80;
+
+/// A decoded image as tightly packed RGBA, the layout the pipeline takes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RgbaFrame {
+    pub pixels: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Encoded pictures of a finished scan: the frame for the result screen and
+/// the smaller copies kept in history.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScanImages {
+    /// The whole frame as a PNG data URL.
+    pub frame_url: String,
+    /// Downscaled JPEG data URL of the frame.
+    pub thumbnail_url: String,
+    /// Downscaled JPEG data URL of each box, in the order the boxes were given.
+    pub crop_urls: Vec<String>,
+}
+
+/// Scale and padding applied when letterboxing a frame into the YOLO input square.
+/// Used to map boxes predicted in letterbox space back onto the source image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Letterbox {
+    pub scale: f32,
+    pub pad_x: f32,
+    pub pad_y: f32,
+    pub source_width: u32,
+    pub source_height: u32,
+}
+
+impl Letterbox {
+    /// Computes the aspect-preserving fit of a source frame into the YOLO input square.
+    pub fn fit(source_width: u32, source_height: u32) -> Self {
+        let target = YOLO_SIZE as f32;
+        let scale = (target / source_width as f32).min(target / source_height as f32);
+        let (new_w, new_h) = scaled_size(source_width, source_height, scale);
+        Self {
+            scale,
+            pad_x: ((YOLO_SIZE as u32 - new_w) / 2) as f32,
+            pad_y: ((YOLO_SIZE as u32 - new_h) / 2) as f32,
+            source_width,
+            source_height,
+        }
+    }
+
+    /// Maps an [x1, y1, x2, y2] box from letterbox space to source image coords,
+    /// clamped to the image bounds.
+    pub fn unmap(&self, bbox: [f32; 4]) -> [f32; 4] {
+        let w = self.source_width as f32;
+        let h = self.source_height as f32;
+        [
+            ((bbox[0] - self.pad_x) / self.scale).clamp(0.0, w),
+            ((bbox[1] - self.pad_y) / self.scale).clamp(0.0, h),
+            ((bbox[2] - self.pad_x) / self.scale).clamp(0.0, w),
+            ((bbox[3] - self.pad_y) / self.scale).clamp(0.0, h),
+        ]
+    }
+}
+
+/// Decodes a JPEG, PNG or WebP file and turns it upright according to its EXIF
+/// orientation, so boxes and crops line up with the photo as the user sees it.
+pub fn decode_image(bytes: &[u8]) -> Result<RgbaFrame, Error> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| Error::Preprocessing(format!("Image read failed: {e}")))?
+        .into_decoder()
+        .map_err(|e| Error::Preprocessing(format!("Unsupported image: {e}")))?;
+    // A broken EXIF block should not make an otherwise readable photo fail.
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut img = DynamicImage::from_decoder(decoder)
+        .map_err(|e| Error::Preprocessing(format!("Image decode failed: {e}")))?;
+    img.apply_orientation(orientation);
+
+    let rgba = img.into_rgba8();
+    Ok(RgbaFrame {
+        width: rgba.width(),
+        height: rgba.height(),
+        pixels: rgba.into_raw(),
+    })
+}
+
+/// Size of a `width` x `height` image downscaled so neither side exceeds
+/// `max_side`, keeping its aspect ratio; unchanged if it already fits.
+pub fn fitted_size(width: u32, height: u32, max_side: u32) -> (u32, u32) {
+    let longest = width.max(height);
+    if longest <= max_side {
+        return (width, height);
+    }
+    let scale = max_side as f32 / longest as f32;
+    (
+        ((width as f32 * scale).round() as u32).max(1),
+        ((height as f32 * scale).round() as u32).max(1),
+    )
+}
+
+/// Downscales `frame` so neither side exceeds `max_side`, keeping its aspect ratio.
+pub fn fit_within(frame: RgbaFrame, max_side: u32) -> Result<RgbaFrame, Error> {
+    let (width, height) = fitted_size(frame.width, frame.height, max_side);
+    if (width, height) == (frame.width, frame.height) {
+        return Ok(frame);
+    }
+
+    let img = RgbaImage::from_raw(frame.width, frame.height, frame.pixels)
+        .ok_or_else(|| Error::Preprocessing("Failed to create image from raw bytes".into()))?;
+    let resized = imageops::resize(&img, width, height, imageops::FilterType::Triangle);
+    Ok(RgbaFrame {
+        pixels: resized.into_raw(),
+        width,
+        height,
+    })
+}
+
+/// Converts raw RGBA pixels to a float32 NCHW tensor for YOLOv8 (640x640, /255 only).
+/// The frame is resized with its aspect ratio preserved and padded with gray,
+/// matching the Ultralytics letterbox; the returned `Letterbox` undoes the mapping.
+pub fn preprocess_for_yolo(
+    rgba_bytes: &[u8],
+    source_width: u32,
+    source_height: u32,
+) -> Result<(Vec<f32>, Letterbox), Error> {
+    // `Letterbox::fit` divides by the frame size.
+    if source_width == 0 || source_height == 0 {
+        return Err(Error::Preprocessing(format!(
+            "Empty {source_width}x{source_height} frame"
+        )));
+    }
+    let img = rgba_to_rgb(rgba_bytes, source_width, source_height)?;
+    let letterbox = Letterbox::fit(source_width, source_height);
+    let (new_w, new_h) = scaled_size(source_width, source_height, letterbox.scale);
+    let resized = imageops::resize(&img, new_w, new_h, imageops::FilterType::Triangle);
+
+    let mut canvas = RgbImage::from_pixel(
+        YOLO_SIZE as u32,
+        YOLO_SIZE as u32,
+        image::Rgb([LETTERBOX_FILL; 3]),
+    );
+    imageops::replace(
+        &mut canvas,
+        &resized,
+        letterbox.pad_x as i64,
+        letterbox.pad_y as i64,
+    );
+
+    let pixels = YOLO_SIZE * YOLO_SIZE;
+    let mut out = vec![0.0f32; 3 * pixels];
+    for (i, pixel) in canvas.pixels().enumerate() {
+        out[i] = pixel[0] as f32 / 255.0;
+        out[pixels + i] = pixel[1] as f32 / 255.0;
+        out[2 * pixels + i] = pixel[2] as f32 / 255.0;
+    }
+    Ok((out, letterbox))
+}
+
+/// Crops a bounding box region from RGBA pixels and preprocesses for MobileNetV2.
+pub fn crop_and_preprocess(
+    rgba_bytes: &[u8],
+    source_width: u32,
+    source_height: u32,
+    bbox: [f32; 4],
+) -> Result<Vec<f32>, Error> {
+    let img = rgba_to_rgb(rgba_bytes, source_width, source_height)?;
+    let cropped = crop_region(&img, bbox);
+    let resized = imageops::resize(
+        &cropped,
+        MOBILENET_SIZE as u32,
+        MOBILENET_SIZE as u32,
+        imageops::FilterType::Triangle,
+    );
+    Ok(normalize_mobilenet(&resized))
+}
+
+/// Crops a bounding box region and returns a base64 PNG data URL for display.
+pub fn crop_to_data_url(
+    rgba_bytes: &[u8],
+    source_width: u32,
+    source_height: u32,
+    bbox: [f32; 4],
+) -> Result<String, Error> {
+    let img = rgba_to_rgb(rgba_bytes, source_width, source_height)?;
+    png_data_url(&crop_region(&img, bbox))
+}
+
+/// Encodes the whole frame as a base64 PNG data URL for display.
+pub fn frame_to_data_url(
+    rgba_bytes: &[u8],
+    source_width: u32,
+    source_height: u32,
+) -> Result<String, Error> {
+    let img = rgba_to_rgb(rgba_bytes, source_width, source_height)?;
+    png_data_url(&img)
+}
+
+/// Encodes everything a finished scan shows and saves for `bboxes`. The
+/// encoders are slow enough on large frames to belong off the main thread.
+pub fn encode_scan_images(
+    rgba_bytes: &[u8],
+    source_width: u32,
+    source_height: u32,
+    bboxes: &[[f32; 4]],
+    thumbnail_side: u32,
+    crop_side: u32,
+) -> Result<ScanImages, Error> {
+    Ok(ScanImages {
+        frame_url: frame_to_data_url(rgba_bytes, source_width, source_height)?,
+        thumbnail_url: thumbnail_data_url(rgba_bytes, source_width, source_height, thumbnail_side)?,
+        crop_urls: bboxes
+            .iter()
+            .map(|&bbox| {
+                crop_thumbnail_data_url(rgba_bytes, source_width, source_height, bbox, crop_side)
+            })
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Downscaled JPEG of the whole frame as a data URL, small enough to keep in history.
+pub fn thumbnail_data_url(
+    rgba_bytes: &[u8],
+    source_width: u32,
+    source_height: u32,
+    max_side: u32,
+) -> Result<String, Error> {
+    let frame = fit_within(
+        RgbaFrame {
+            pixels: rgba_bytes.to_vec(),
+            width: source_width,
+            height: source_height,
+        },
+        max_side,
+    )?;
+    jpeg_data_url(&rgba_to_rgb(&frame.pixels, frame.width, frame.height)?)
+}
+
+/// Downscaled JPEG of a bounding box region, the history counterpart of `crop_to_data_url`.
+pub fn crop_thumbnail_data_url(
+    rgba_bytes: &[u8],
+    source_width: u32,
+    source_height: u32,
+    bbox: [f32; 4],
+    max_side: u32,
+) -> Result<String, Error> {
+    let img = RgbaImage::from_raw(source_width, source_height, rgba_bytes.to_vec())
+        .ok_or_else(|| Error::Preprocessing("Failed to create image from raw bytes".into()))?;
+    let cropped = crop_region(&img, bbox);
+    let frame = fit_within(
+        RgbaFrame {
+            width: cropped.width(),
+            height: cropped.height(),
+            pixels: cropped.into_raw(),
+        },
+        max_side,
+    )?;
+    jpeg_data_url(&rgba_to_rgb(&frame.pixels, frame.width, frame.height)?)
+}
+
+fn jpeg_data_url(img: &RgbImage) -> Result<String, Error> {
+    let mut buf = Vec::new();
+    JpegEncoder::new_with_quality(&mut buf, THUMBNAIL_QUALITY)
+        .write_image(
+            img.as_raw(),
+            img.width(),
+            img.height(),
+            image::ExtendedColorType::Rgb8,
+        )
+        .map_err(|e| Error::Preprocessing(format!("JPEG encode failed: {e}")))?;
+    let b64 = STANDARD.encode(&buf);
+    Ok(format!("data:image/jpeg;base64,{b64}"))
+}
+
+fn png_data_url(img: &RgbImage) -> Result<String, Error> {
+    let mut buf = Vec::new();
+    PngEncoder::new(&mut buf)
+        .write_image(
+            img.as_raw(),
+            img.width(),
+            img.height(),
+            image::ExtendedColorType::Rgb8,
+        )
+        .map_err(|e| Error::Preprocessing(format!("PNG encode failed: {e}")))?;
+    let b64 = STANDARD.encode(&buf);
+    Ok(format!("data:image/png;base64,{b64}"))
+}
+
+/// Largest centered square of a frame as an [x1, y1, x2, y2] box.
+pub fn center_square(width: u32, height: u32) -> [f32; 4] {
+    let side = width.min(height) as f32;
+    let x1 = (width as f32 - side) / 2.0;
+    let y1 = (height as f32 - side) / 2.0;
+    [x1, y1, x1 + side, y1 + side]
+}
+
+fn rgba_to_rgb(rgba: &[u8], w: u32, h: u32) -> Result<RgbImage, Error> {
+    let rgb: Vec<u8> = rgba.chunks(4).flat_map(|p| [p[0], p[1], p[2]]).collect();
+    RgbImage::from_raw(w, h, rgb)
+        .ok_or_else(|| Error::Preprocessing("Failed to create image from raw bytes".into()))
+}
+
+/// Size of the source frame after scaling, kept at least 1px on each side.
+fn scaled_size(w: u32, h: u32, scale: f32) -> (u32, u32) {
+    let new_w = ((w as f32 * scale).round() as u32).clamp(1, YOLO_SIZE as u32);
+    let new_h = ((h as f32 * scale).round() as u32).clamp(1, YOLO_SIZE as u32);
+    (new_w, new_h)
+}
+
+fn crop_region<P: Pixel + 'static>(
+    img: &ImageBuffer<P, Vec<P::Subpixel>>,
+    bbox: [f32; 4],
+) -> ImageBuffer<P, Vec<P::Subpixel>> {
+    let x1 = (bbox[0].max(0.0) as u32).min(img.width().saturating_sub(1));
+    let y1 = (bbox[1].max(0.0) as u32).min(img.height().saturating_sub(1));
+    let x2 = (bbox[2].max(0.0).ceil() as u32).min(img.width());
+    let y2 = (bbox[3].max(0.0).ceil() as u32).min(img.height());
+    let w = (x2.saturating_sub(x1)).max(1);
+    let h = (y2.saturating_sub(y1)).max(1);
+    imageops::crop_imm(img, x1, y1, w, h).to_image()
+}
+
+fn normalize_mobilenet(img: &image::ImageBuffer<image::Rgb<u8>, Vec<u8>>) -> Vec<f32> {
+    let pixels = (img.width() * img.height()) as usize;
+    let mut out = vec![0.0f32; 3 * pixels];
+    for (i, pixel) in img.pixels().enumerate() {
+        out[i] = (pixel[0] as f32 / 255.0 - MOBILENET_MEAN) / MOBILENET_STD;
+        out[pixels + i] = (pixel[1] as f32 / 255.0 - MOBILENET_MEAN) / MOBILENET_STD;
+        out[2 * pixels + i] = (pixel[2] as f32 / 255.0 - MOBILENET_MEAN) / MOBILENET_STD;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 3] = [255, 0, 0];
+    const GREEN: [u8; 3] = [0, 255, 0];
+    const BLUE: [u8; 3] = [0, 0, 255];
+    const WHITE: [u8; 3] = [255, 255, 255];
+
+    /// 32x16 image with one color per quadrant: red, green / blue, white.
+    fn quadrants() -> RgbImage {
+        RgbImage::from_fn(32, 16, |x, y| {
+            image::Rgb(match (x < 16, y < 8) {
+                (true, true) => RED,
+                (false, true) => GREEN,
+                (true, false) => BLUE,
+                (false, false) => WHITE,
+            })
+        })
+    }
+
+    /// Minimal little-endian EXIF block holding only the Orientation tag.
+    fn exif_orientation(value: u16) -> Vec<u8> {
+        let mut exif = b"II*\0".to_vec();
+        exif.extend_from_slice(&8u32.to_le_bytes());
+        exif.extend_from_slice(&1u16.to_le_bytes());
+        exif.extend_from_slice(&0x0112u16.to_le_bytes());
+        exif.extend_from_slice(&3u16.to_le_bytes());
+        exif.extend_from_slice(&1u32.to_le_bytes());
+        exif.extend_from_slice(&value.to_le_bytes());
+        exif.extend_from_slice(&[0, 0]);
+        exif.extend_from_slice(&0u32.to_le_bytes());
+        exif
+    }
+
+    fn encode(mut encoder: impl ImageEncoder, orientation: Option<u16>) {
+        if let Some(value) = orientation {
+            encoder.set_exif_metadata(exif_orientation(value)).unwrap();
+        }
+        let img = quadrants();
+        encoder
+            .write_image(
+                img.as_raw(),
+                img.width(),
+                img.height(),
+                image::ExtendedColorType::Rgb8,
+            )
+            .unwrap();
+    }
+
+    fn png(orientation: Option<u16>) -> Vec<u8> {
+        let mut buf = Vec::new();
+        encode(PngEncoder::new(&mut buf), orientation);
+        buf
+    }
+
+    fn jpeg(orientation: Option<u16>) -> Vec<u8> {
+        let mut buf = Vec::new();
+        encode(JpegEncoder::new_with_quality(&mut buf, 95), orientation);
+        buf
+    }
+
+    /// Colors at the centers of the four quadrants, clockwise from top-left.
+    fn corner_colors(frame: &RgbaFrame) -> [[u8; 3]; 4] {
+        let at = |x: u32, y: u32| {
+            let i = ((y * frame.width + x) * 4) as usize;
+            [frame.pixels[i], frame.pixels[i + 1], frame.pixels[i + 2]]
+        };
+        let (w, h) = (frame.width, frame.height);
+        [
+            at(w / 4, h / 4),
+            at(3 * w / 4, h / 4),
+            at(3 * w / 4, 3 * h / 4),
+            at(w / 4, 3 * h / 4),
+        ]
+    }
+
+    fn assert_close(actual: [[u8; 3]; 4], expected: [[u8; 3]; 4]) {
+        let close = actual
+            .iter()
+            .flatten()
+            .zip(expected.iter().flatten())
+            .all(|(a, e)| a.abs_diff(*e) <= 16);
+        assert!(close, "expected {expected:?}, got {actual:?}");
+    }
+
+    #[test]
+    fn decode_png_keeps_pixels_without_exif() {
+        let frame = decode_image(&png(None)).unwrap();
+        let expected = DynamicImage::ImageRgb8(quadrants()).into_rgba8();
+        assert_eq!((frame.width, frame.height), (32, 16));
+        assert_eq!(frame.pixels, expected.into_raw());
+    }
+
+    #[test]
+    fn decode_applies_exif_orientation() {
+        // EXIF value, output size, quadrant colors clockwise from top-left.
+        let cases = [
+            (1, (32, 16), [RED, GREEN, WHITE, BLUE]),
+            (3, (32, 16), [WHITE, BLUE, RED, GREEN]),
+            (6, (16, 32), [BLUE, RED, GREEN, WHITE]),
+            (8, (16, 32), [GREEN, WHITE, BLUE, RED]),
+        ];
+        for (value, size, colors) in cases {
+            for (format, bytes) in [("png", png(Some(value))), ("jpeg", jpeg(Some(value)))] {
+                let frame = decode_image(&bytes).unwrap();
+                assert_eq!(
+                    (frame.width, frame.height),
+                    size,
+                    "{format} orientation {value}"
+                );
+                assert_close(corner_colors(&frame), colors);
+            }
+        }
+    }
+
+    /// Checked-in camera-style files, so decoding is tested against bytes this
+    /// module did not just encode: the 32x16 quadrants with EXIF orientation.
+    #[test]
+    fn decode_rotates_checked_in_files() {
+        let cases = [
+            (
+                "jpeg orientation 6",
+                include_bytes!("../tests/images/quadrants-orientation-6.jpg").as_slice(),
+                [BLUE, RED, GREEN, WHITE],
+            ),
+            (
+                "webp orientation 8",
+                include_bytes!("../tests/images/quadrants-orientation-8.webp").as_slice(),
+                [GREEN, WHITE, BLUE, RED],
+            ),
+        ];
+        for (name, bytes, colors) in cases {
+            let frame = decode_image(bytes).unwrap();
+            assert_eq!((frame.width, frame.height), (16, 32), "{name}");
+            assert_close(corner_colors(&frame), colors);
+        }
+    }
+
+    #[test]
+    fn yolo_preprocessing_rejects_empty_frames() {
+        for (width, height) in [(0, 0), (0, 16), (16, 0)] {
+            assert!(matches!(
+                preprocess_for_yolo(&[], width, height),
+                Err(Error::Preprocessing(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn decode_rejects_unknown_data() {
+        assert!(matches!(
+            decode_image(b"not an image"),
+            Err(Error::Preprocessing(_))
+        ));
+    }
+
+    #[test]
+    fn fit_within_keeps_aspect_ratio() {
+        let frame = decode_image(&png(None)).unwrap();
+        let small = fit_within(frame.clone(), 8).unwrap();
+        assert_eq!((small.width, small.height), (8, 4));
+        assert_eq!(small.pixels.len(), 8 * 4 * 4);
+        assert_eq!(fit_within(frame.clone(), 32).unwrap(), frame);
+        assert_eq!(fitted_size(4000, 3000, 2048), (2048, 1536));
+        assert_eq!(fitted_size(3000, 1, 2048), (2048, 1));
+    }
+
+    #[test]
+    fn crop_thumbnail_is_a_small_jpeg() {
+        let frame = decode_image(&png(None)).unwrap();
+        let url =
+            crop_thumbnail_data_url(&frame.pixels, 32, 16, [0.0, 0.0, 16.0, 16.0], 8).unwrap();
+        let b64 = url.strip_prefix("data:image/jpeg;base64,").unwrap();
+        let crop = decode_image(&STANDARD.decode(b64).unwrap()).unwrap();
+        assert_eq!((crop.width, crop.height), (8, 8));
+    }
+}
@@ -0,0 +1,11 @@
+//! Recognition pipeline behind Netthinne: image preprocessing, YOLOv8 detection,
+//! MobileNetV2 classification and the bilingual label tables.
+//!
+//! Both models are compiled into the library, and nothing here depends on a
+//! browser, so the same code runs in the web app's worker and natively.
+
+mod error;
+pub mod image_utils;
+pub mod ml;
+
+pub use error::Error;
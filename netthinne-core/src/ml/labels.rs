@@ -0,0 +1,72 @@
+//! Norwegian label metadata generated by `build.rs` from the `*_norsk.tsv` files.
+
+use serde::{Deserialize, Serialize};
+
+/// Grammatical gender of a Norwegian noun.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Gender {
+    Masculine,
+    Feminine,
+    Neuter,
+}
+
+impl Gender {
+    /// Indefinite article: en, ei or et.
+    pub fn article(&self) -> &'static str {
+        match self {
+            Gender::Masculine => "en",
+            Gender::Feminine => "ei",
+            Gender::Neuter => "et",
+        }
+    }
+
+    /// Norwegian name of the gender, as shown in the inflection table.
+    pub fn name_no(&self) -> &'static str {
+        match self {
+            Gender::Masculine => "hankjønn",
+            Gender::Feminine => "hunkjønn",
+            Gender::Neuter => "intetkjønn",
+        }
+    }
+}
+
+/// One row of a Norwegian label table, embedded at build time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Label {
+    /// Indefinite singular, the form shown as the label.
+    pub noun: &'static str,
+    pub gender: Gender,
+    pub definite: &'static str,
+    /// `None` for nouns without a plural, such as mass nouns.
+    pub plural: Option<&'static str>,
+    pub definite_plural: Option<&'static str>,
+}
+
+impl Label {
+    pub fn inflection(&self) -> Inflection {
+        Inflection {
+            gender: self.gender,
+            definite: self.definite.to_string(),
+            plural: self.plural.map(str::to_string),
+            definite_plural: self.definite_plural.map(str::to_string),
+        }
+    }
+}
+
+/// Gender and inflected forms of a predicted Norwegian label.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Inflection {
+    pub gender: Gender,
+    pub definite: String,
+    pub plural: Option<String>,
+    pub definite_plural: Option<String>,
+}
+
+/// Noun and inflection for class `idx`, falling back to "ukjent" without forms.
+pub fn lookup(table: &[Label], idx: usize) -> (String, Option<Inflection>) {
+    match table.get(idx) {
+        Some(label) => (label.noun.to_string(), Some(label.inflection())),
+        None => ("ukjent".to_string(), None),
+    }
+}
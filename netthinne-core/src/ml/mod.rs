@@ -0,0 +1,166 @@
+mod labels;
+mod recognition;
+mod segmentation;
+
+pub use labels::{Gender, Inflection};
+pub use recognition::Prediction;
+pub use segmentation::{DetectionConfig, Suppression};
+
+use serde::{Deserialize, Serialize};
+
+use crate::image_utils::{
+    center_square, crop_and_preprocess, crop_to_data_url, preprocess_for_yolo,
+};
+use crate::Error;
+
+/// COCO class YOLO assigned to a box.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct YoloLabel {
+    pub class_idx: usize,
+    pub confidence: f32,
+    pub label_en: String,
+    pub label_no: String,
+    /// Absent in scans saved before labels carried grammar.
+    #[serde(default)]
+    pub inflection: Option<Inflection>,
+}
+
+/// A YOLO box without the classification stage, as drawn in live mode.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LabeledBox {
+    /// Box in source image pixels as [x1, y1, x2, y2].
+    pub bbox: [f32; 4],
+    pub label: YoloLabel,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DetectedObject {
+    pub image_data_url: String,
+    /// Box in source image pixels as [x1, y1, x2, y2].
+    pub bbox: [f32; 4],
+    /// `None` when YOLO found nothing and the whole image was classified instead.
+    pub yolo: Option<YoloLabel>,
+    /// ImageNet-1k classes for the crop, most probable first.
+    pub inet_predictions: Vec<Prediction>,
+}
+
+/// Pipeline step reported while `process_image` runs.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum Stage {
+    Detecting,
+    Classifying { done: usize, total: usize },
+}
+
+impl Stage {
+    /// Rough share of the pipeline finished once this stage is reached, in 0..=1.
+    pub fn fraction(&self) -> f32 {
+        match *self {
+            Stage::Detecting => 0.0,
+            Stage::Classifying { done, total } => (1 + done) as f32 / (1 + total.max(1)) as f32,
+        }
+    }
+}
+
+/// Loads both embedded models so the first capture does not pay for it.
+pub fn warm_up() {
+    segmentation::load_model();
+    recognition::load_model();
+}
+
+/// Two-stage pipeline: YOLO detection then MobileNetV2 classification per crop.
+/// `on_progress` is called before each model run.
+pub fn process_image(
+    rgba_bytes: &[u8],
+    width: u32,
+    height: u32,
+    config: &DetectionConfig,
+    mut on_progress: impl FnMut(Stage),
+) -> Result<Vec<DetectedObject>, Error> {
+    let (yolo_input, letterbox) = preprocess_for_yolo(rgba_bytes, width, height)?;
+
+    on_progress(Stage::Detecting);
+    let detections = segmentation::detect(yolo_input, &letterbox, config)?;
+
+    if detections.is_empty() {
+        on_progress(Stage::Classifying { done: 0, total: 1 });
+        return Ok(vec![classify_whole_image(rgba_bytes, width, height)?]);
+    }
+
+    let total = detections.len();
+    detections
+        .into_iter()
+        .enumerate()
+        .map(|(done, det)| {
+            on_progress(Stage::Classifying { done, total });
+            let (image_data_url, inet_predictions) =
+                classify_region(rgba_bytes, width, height, det.bbox)?;
+
+            Ok(DetectedObject {
+                image_data_url,
+                bbox: det.bbox,
+                yolo: Some(yolo_label(&det)),
+                inet_predictions,
+            })
+        })
+        .collect()
+}
+
+/// YOLO stage only, for live preview frames where MobileNet would be too slow.
+pub fn detect_objects(
+    rgba_bytes: &[u8],
+    width: u32,
+    height: u32,
+    config: &DetectionConfig,
+) -> Result<Vec<LabeledBox>, Error> {
+    let (yolo_input, letterbox) = preprocess_for_yolo(rgba_bytes, width, height)?;
+    Ok(segmentation::detect(yolo_input, &letterbox, config)?
+        .iter()
+        .map(|det| LabeledBox {
+            bbox: det.bbox,
+            label: yolo_label(det),
+        })
+        .collect())
+}
+
+fn yolo_label(det: &segmentation::Detection) -> YoloLabel {
+    let (label_no, inflection) = segmentation::label_no(det.class_idx);
+    YoloLabel {
+        class_idx: det.class_idx,
+        confidence: det.confidence,
+        label_en: segmentation::label_en(det.class_idx),
+        label_no,
+        inflection,
+    }
+}
+
+/// Fallback when YOLO finds nothing: classify the center square of the frame,
+/// which matches how MobileNet was trained on ImageNet.
+fn classify_whole_image(
+    rgba_bytes: &[u8],
+    width: u32,
+    height: u32,
+) -> Result<DetectedObject, Error> {
+    let bbox = center_square(width, height);
+    let (image_data_url, inet_predictions) = classify_region(rgba_bytes, width, height, bbox)?;
+
+    Ok(DetectedObject {
+        image_data_url,
+        bbox,
+        yolo: None,
+        inet_predictions,
+    })
+}
+
+/// Crops `bbox` for display and runs MobileNetV2 on it.
+fn classify_region(
+    rgba_bytes: &[u8],
+    width: u32,
+    height: u32,
+    bbox: [f32; 4],
+) -> Result<(String, Vec<Prediction>), Error> {
+    let image_data_url = crop_to_data_url(rgba_bytes, width, height, bbox)?;
+    let crop_input = crop_and_preprocess(rgba_bytes, width, height, bbox)?;
+    let inet_predictions = recognition::recognize(crop_input, recognition::DEFAULT_TOP_K)?;
+    Ok((image_data_url, inet_predictions))
+}
@@ -0,0 +1,117 @@
+pub mod model {
+    include!(concat!(env!("OUT_DIR"), "/ml/mobilenet_v2_1_4_fp32.rs"));
+}
+
+mod labels {
+    include!(concat!(env!("OUT_DIR"), "/ml/labels_in1k.rs"));
+}
+
+mod labels_norsk {
+    use crate::ml::labels::{Gender, Label};
+    include!(concat!(env!("OUT_DIR"), "/ml/labels_in1k_norsk.rs"));
+}
+
+use std::cell::RefCell;
+
+use burn::backend::NdArray;
+use burn::tensor::Tensor;
+use model::Model;
+use serde::{Deserialize, Serialize};
+
+use crate::ml::labels::{self as norsk, Inflection};
+use crate::Error;
+
+type Backend = NdArray<f32>;
+
+thread_local! {
+    static MODEL: RefCell<Option<Model<Backend>>> = const { RefCell::new(None) };
+}
+
+/// Number of ImageNet classes returned by default.
+pub const DEFAULT_TOP_K: usize = 5;
+
+/// One ImageNet class with its softmax probability.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Prediction {
+    pub label_en: String,
+    pub label_no: String,
+    /// Absent in scans saved before labels carried grammar.
+    #[serde(default)]
+    pub inflection: Option<Inflection>,
+    pub probability: f32,
+}
+
+/// Builds the embedded model if it is not loaded yet.
+pub fn load_model() {
+    MODEL.with(|cell| {
+        let mut guard = cell.borrow_mut();
+        if guard.is_none() {
+            *guard = Some(Model::from_embedded(&Default::default()));
+        }
+    });
+}
+
+/// Runs MobileNetV2 inference on preprocessed NCHW float data.
+/// Returns the `top_k` most likely classes, most probable first.
+pub fn recognize(float_data: Vec<f32>, top_k: usize) -> Result<Vec<Prediction>, Error> {
+    let device = Default::default();
+    load_model();
+
+    let input =
+        Tensor::<Backend, 1>::from_floats(float_data.as_slice(), &device).reshape([1, 3, 224, 224]);
+
+    let output = MODEL.with(|cell| {
+        let guard = cell.borrow();
+        guard.as_ref().unwrap().forward(input)
+    });
+
+    let logits: Vec<f32> = output
+        .into_data()
+        .to_vec()
+        .map_err(|e| Error::Inference(format!("MobileNet output: {e:?}")))?;
+    let probabilities = softmax(&logits);
+
+    let mut ranked: Vec<usize> = (0..probabilities.len()).collect();
+    ranked.sort_by(|&a, &b| probabilities[b].total_cmp(&probabilities[a]));
+
+    Ok(ranked
+        .into_iter()
+        .take(top_k)
+        .map(|class_idx| {
+            let (label_no, inflection) = norsk::lookup(labels_norsk::LABELS_IN1K_NORSK, class_idx);
+            Prediction {
+                label_en: labels::LABELS_IN1K
+                    .get(class_idx)
+                    .unwrap_or(&"unknown")
+                    .to_string(),
+                label_no,
+                inflection,
+                probability: probabilities[class_idx],
+            }
+        })
+        .collect())
+}
+
+fn softmax(logits: &[f32]) -> Vec<f32> {
+    let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let exps: Vec<f32> = logits.iter().map(|&x| (x - max).exp()).collect();
+    let sum: f32 = exps.iter().sum();
+    exps.into_iter().map(|e| e / sum).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_output_matches_label_tables() {
+        let device = Default::default();
+        let input = Tensor::<Backend, 4>::zeros([1, 3, 224, 224], &device);
+        let classes = Model::<Backend>::from_embedded(&device)
+            .forward(input)
+            .dims()[1];
+
+        assert_eq!(labels::LABELS_IN1K.len(), classes);
+        assert_eq!(labels_norsk::LABELS_IN1K_NORSK.len(), classes);
+    }
+}
@@ -0,0 +1,674 @@
+pub mod yolo_model {
+    include!(concat!(env!("OUT_DIR"), "/ml/yolov8n.rs"));
+}
+
+mod labels_yolo {
+    include!(concat!(env!("OUT_DIR"), "/ml/labels_yolo.rs"));
+}
+
+mod labels_yolo_norsk {
+    use crate::ml::labels::{Gender, Label};
+    include!(concat!(env!("OUT_DIR"), "/ml/labels_yolo_norsk.rs"));
+}
+
+use std::cell::RefCell;
+use std::ops::RangeInclusive;
+
+use burn::backend::NdArray;
+use burn::tensor::Tensor;
+use serde::{Deserialize, Serialize};
+use yolo_model::Model;
+
+use crate::image_utils::Letterbox;
+use crate::ml::labels::{self as norsk, Inflection};
+use crate::Error;
+
+type Backend = NdArray<f32>;
+
+const NUM_CLASSES: usize = 80;
+/// Candidate boxes per image: one per cell of the 80x80, 40x40 and 20x20 grids.
+const NUM_ANCHORS: usize = 8400;
+/// Gaussian decay width for soft-NMS; smaller values suppress overlaps harder.
+const SOFT_NMS_SIGMA: f32 = 0.5;
+
+/// How overlapping candidate boxes are resolved after decoding.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Suppression {
+    /// Drop boxes that overlap a stronger box of the same class.
+    #[default]
+    PerClass,
+    /// Drop boxes that overlap a stronger box of any class.
+    ClassAgnostic,
+    /// Gaussian soft-NMS: decay the confidence of overlapping boxes of any class
+    /// instead of dropping them outright.
+    Soft,
+}
+
+impl Suppression {
+    pub const ALL: [Suppression; 3] = [
+        Suppression::PerClass,
+        Suppression::ClassAgnostic,
+        Suppression::Soft,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Suppression::PerClass => "per-class",
+            Suppression::ClassAgnostic => "class-agnostic",
+            Suppression::Soft => "soft",
+        }
+    }
+}
+
+impl std::fmt::Display for Suppression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Suppression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Suppression::ALL
+            .into_iter()
+            .find(|mode| mode.as_str() == s)
+            .ok_or_else(|| format!("unknown suppression mode: {s}"))
+    }
+}
+
+/// Tunable YOLO post-processing parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DetectionConfig {
+    /// Minimum class score for a candidate box to be kept.
+    pub conf_threshold: f32,
+    /// Overlap above which the weaker of two boxes is suppressed.
+    /// Ignored by soft-NMS, which decays by overlap instead.
+    pub iou_threshold: f32,
+    pub max_detections: usize,
+    pub suppression: Suppression,
+}
+
+impl Default for DetectionConfig {
+    fn default() -> Self {
+        Self {
+            conf_threshold: 0.25,
+            iou_threshold: 0.45,
+            max_detections: 3,
+            suppression: Suppression::PerClass,
+        }
+    }
+}
+
+impl DetectionConfig {
+    /// Values offered for either threshold on the settings screen.
+    pub const THRESHOLD_RANGE: RangeInclusive<f32> = 0.05..=0.95;
+    /// Values offered for `max_detections` on the settings screen.
+    pub const MAX_DETECTIONS_RANGE: RangeInclusive<usize> = 1..=20;
+
+    /// Pulls every field into the ranges the settings screen offers.
+    /// Non-finite thresholds fall back to the defaults.
+    pub fn clamped(self) -> Self {
+        let defaults = Self::default();
+        let threshold = |value: f32, default: f32| {
+            if value.is_finite() {
+                value.clamp(*Self::THRESHOLD_RANGE.start(), *Self::THRESHOLD_RANGE.end())
+            } else {
+                default
+            }
+        };
+        Self {
+            conf_threshold: threshold(self.conf_threshold, defaults.conf_threshold),
+            iou_threshold: threshold(self.iou_threshold, defaults.iou_threshold),
+            max_detections: self.max_detections.clamp(
+                *Self::MAX_DETECTIONS_RANGE.start(),
+                *Self::MAX_DETECTIONS_RANGE.end(),
+            ),
+            suppression: self.suppression,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Detection {
+    pub bbox: [f32; 4],
+    pub class_idx: usize,
+    pub confidence: f32,
+}
+
+thread_local! {
+    static YOLO: RefCell<Option<Model<Backend>>> = const { RefCell::new(None) };
+}
+
+/// Builds the embedded model if it is not loaded yet.
+pub fn load_model() {
+    YOLO.with(|cell| {
+        let mut guard = cell.borrow_mut();
+        if guard.is_none() {
+            *guard = Some(Model::from_embedded(&Default::default()));
+        }
+    });
+}
+
+/// Runs YOLOv8n object detection on preprocessed 640x640 NCHW float data.
+/// `letterbox` describes how the frame was fitted into the input square.
+/// Returns up to `config.max_detections` sorted by confidence descending.
+pub fn detect(
+    float_data: Vec<f32>,
+    letterbox: &Letterbox,
+    config: &DetectionConfig,
+) -> Result<Vec<Detection>, Error> {
+    let device = Default::default();
+    load_model();
+
+    let input =
+        Tensor::<Backend, 1>::from_floats(float_data.as_slice(), &device).reshape([1, 3, 640, 640]);
+
+    let output = YOLO.with(|cell| {
+        let guard = cell.borrow();
+        guard.as_ref().unwrap().forward(input)
+    });
+
+    // YOLOv8 output: [1, 84, 8400] -> squeeze to [84, 8400] -> transpose to [8400, 84]
+    let raw: Vec<f32> = output
+        .into_data()
+        .to_vec()
+        .map_err(|e| Error::Inference(format!("YOLO output: {e:?}")))?;
+    let candidates = decode_and_filter(&raw, letterbox, config.conf_threshold);
+    Ok(non_maximum_suppression(candidates, config))
+}
+
+pub fn label_en(idx: usize) -> String {
+    labels_yolo::LABELS_YOLO
+        .get(idx)
+        .unwrap_or(&"unknown")
+        .to_string()
+}
+
+/// Norwegian noun for class `idx` with its inflected forms.
+pub fn label_no(idx: usize) -> (String, Option<Inflection>) {
+    norsk::lookup(labels_yolo_norsk::LABELS_YOLO_NORSK, idx)
+}
+
+/// Decodes raw [84, 8400] output into filtered detections in original image coords.
+/// Candidates whose best score or box is NaN are dropped rather than propagated.
+fn decode_and_filter(raw: &[f32], letterbox: &Letterbox, conf_threshold: f32) -> Vec<Detection> {
+    let mut detections = Vec::new();
+
+    // raw is in [84, 8400] layout: row-major, so raw[row * NUM_ANCHORS + col]
+    for col in 0..NUM_ANCHORS {
+        let cx = raw[col];
+        let cy = raw[NUM_ANCHORS + col];
+        let w = raw[2 * NUM_ANCHORS + col];
+        let h = raw[3 * NUM_ANCHORS + col];
+
+        // A NaN score never compares greater, so it cannot win a candidate.
+        let mut max_score: f32 = 0.0;
+        let mut max_class: usize = 0;
+        for cls in 0..NUM_CLASSES {
+            let score = raw[(4 + cls) * NUM_ANCHORS + col];
+            if score > max_score {
+                max_score = score;
+                max_class = cls;
+            }
+        }
+
+        if max_score < conf_threshold {
+            continue;
+        }
+
+        let bbox = letterbox.unmap([cx - w / 2.0, cy - h / 2.0, cx + w / 2.0, cy + h / 2.0]);
+        if bbox.iter().any(|v| v.is_nan()) {
+            continue;
+        }
+
+        detections.push(Detection {
+            bbox,
+            class_idx: max_class,
+            confidence: max_score,
+        });
+    }
+
+    detections
+}
+
+/// Keep top detections, resolving overlaps with the configured strategy.
+fn non_maximum_suppression(
+    mut detections: Vec<Detection>,
+    config: &DetectionConfig,
+) -> Vec<Detection> {
+    detections.retain(|det| !det.confidence.is_nan());
+    match config.suppression {
+        Suppression::PerClass => hard_suppression(detections, config, true),
+        Suppression::ClassAgnostic => hard_suppression(detections, config, false),
+        Suppression::Soft => soft_suppression(detections, config),
+    }
+}
+
+/// Classic greedy NMS. With `same_class_only`, boxes of different classes never
+/// suppress each other.
+fn hard_suppression(
+    mut detections: Vec<Detection>,
+    config: &DetectionConfig,
+    same_class_only: bool,
+) -> Vec<Detection> {
+    detections.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+    let mut keep = Vec::new();
+    let mut suppressed = vec![false; detections.len()];
+
+    for i in 0..detections.len() {
+        if keep.len() >= config.max_detections {
+            break;
+        }
+        if suppressed[i] {
+            continue;
+        }
+        keep.push(i);
+        for j in (i + 1)..detections.len() {
+            if suppressed[j]
+                || (same_class_only && detections[j].class_idx != detections[i].class_idx)
+            {
+                continue;
+            }
+            if intersection_over_union(&detections[i].bbox, &detections[j].bbox)
+                > config.iou_threshold
+            {
+                suppressed[j] = true;
+            }
+        }
+    }
+
+    keep.into_iter().map(|i| detections[i].clone()).collect()
+}
+
+/// Gaussian soft-NMS (Bodla et al., 2017): each pick decays the confidence of the
+/// remaining boxes by exp(-iou^2 / sigma); boxes that fall below the confidence
+/// threshold are dropped. Kept detections report their decayed confidence.
+fn soft_suppression(mut remaining: Vec<Detection>, config: &DetectionConfig) -> Vec<Detection> {
+    let mut keep = Vec::new();
+
+    while keep.len() < config.max_detections {
+        let Some(best_idx) = remaining
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.confidence.total_cmp(&b.1.confidence))
+            .map(|(i, _)| i)
+        else {
+            break;
+        };
+        let best = remaining.swap_remove(best_idx);
+
+        for det in remaining.iter_mut() {
+            let iou = intersection_over_union(&best.bbox, &det.bbox);
+            det.confidence *= (-(iou * iou) / SOFT_NMS_SIGMA).exp();
+        }
+        remaining.retain(|det| det.confidence >= config.conf_threshold);
+        keep.push(best);
+    }
+
+    keep
+}
+
+/// Zero for boxes without area and for NaN coordinates, so neither suppresses anything.
+fn intersection_over_union(a: &[f32; 4], b: &[f32; 4]) -> f32 {
+    let x1 = a[0].max(b[0]);
+    let y1 = a[1].max(b[1]);
+    let x2 = a[2].min(b[2]);
+    let y2 = a[3].min(b[3]);
+    let inter = (x2 - x1).max(0.0) * (y2 - y1).max(0.0);
+    let area_a = (a[2] - a[0]) * (a[3] - a[1]);
+    let area_b = (b[2] - b[0]) * (b[3] - b[1]);
+    let union = area_a + area_b - inter;
+    if union > 0.0 {
+        inter / union
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn det(bbox: [f32; 4], class_idx: usize, confidence: f32) -> Detection {
+        Detection {
+            bbox,
+            class_idx,
+            confidence,
+        }
+    }
+
+    fn config(suppression: Suppression) -> DetectionConfig {
+        DetectionConfig {
+            max_detections: 10,
+            suppression,
+            ..DetectionConfig::default()
+        }
+    }
+
+    /// Letterbox for a 640x640 source, so model space equals image space.
+    fn identity() -> Letterbox {
+        Letterbox::fit(640, 640)
+    }
+
+    /// Synthetic [84, 8400] model output holding the given candidates as
+    /// (anchor, [cx, cy, w, h], class, score); every other value is zero.
+    fn raw_output(candidates: &[(usize, [f32; 4], usize, f32)]) -> Vec<f32> {
+        let mut raw = vec![0.0; (4 + NUM_CLASSES) * NUM_ANCHORS];
+        for &(anchor, xywh, class_idx, score) in candidates {
+            for (row, value) in xywh.into_iter().enumerate() {
+                raw[row * NUM_ANCHORS + anchor] = value;
+            }
+            raw[(4 + class_idx) * NUM_ANCHORS + anchor] = score;
+        }
+        raw
+    }
+
+    #[test]
+    fn decode_reads_box_and_class_from_their_rows() {
+        let raw = raw_output(&[(7, [100.0, 200.0, 40.0, 60.0], 41, 0.9)]);
+        let decoded = decode_and_filter(&raw, &identity(), 0.25);
+        assert_eq!(decoded, vec![det([80.0, 170.0, 120.0, 230.0], 41, 0.9)]);
+    }
+
+    #[test]
+    fn decode_reads_the_last_anchor_and_class() {
+        let last = NUM_ANCHORS - 1;
+        let raw = raw_output(&[(last, [320.0, 320.0, 20.0, 20.0], NUM_CLASSES - 1, 0.7)]);
+        let decoded = decode_and_filter(&raw, &identity(), 0.25);
+        assert_eq!(decoded, vec![det([310.0, 310.0, 330.0, 330.0], 79, 0.7)]);
+    }
+
+    #[test]
+    fn decode_keeps_the_best_class_per_anchor() {
+        let xywh = [50.0, 50.0, 20.0, 20.0];
+        let raw = raw_output(&[(0, xywh, 3, 0.4), (0, xywh, 56, 0.8), (0, xywh, 60, 0.6)]);
+        let decoded = decode_and_filter(&raw, &identity(), 0.25);
+        assert_eq!(decoded.len(), 1);
+        assert_eq!((decoded[0].class_idx, decoded[0].confidence), (56, 0.8));
+    }
+
+    #[test]
+    fn decode_filters_by_conf_threshold_inclusively() {
+        let xywh = [50.0, 50.0, 20.0, 20.0];
+        let raw = raw_output(&[(0, xywh, 0, 0.2), (1, xywh, 0, 0.25), (2, xywh, 0, 0.3)]);
+        let confidences: Vec<f32> = decode_and_filter(&raw, &identity(), 0.25)
+            .iter()
+            .map(|d| d.confidence)
+            .collect();
+        assert_eq!(confidences, vec![0.25, 0.3]);
+    }
+
+    #[test]
+    fn decode_maps_boxes_back_through_the_letterbox() {
+        // 1280x640 is scaled by 0.5 and padded by 160 rows above and below.
+        let letterbox = Letterbox::fit(1280, 640);
+        let raw = raw_output(&[
+            (0, [320.0, 320.0, 100.0, 100.0], 0, 0.9),
+            (1, [10.0, 170.0, 40.0, 40.0], 0, 0.9),
+        ]);
+        let boxes: Vec<[f32; 4]> = decode_and_filter(&raw, &letterbox, 0.25)
+            .iter()
+            .map(|d| d.bbox)
+            .collect();
+        assert_eq!(
+            boxes,
+            vec![[540.0, 220.0, 740.0, 420.0], [0.0, 0.0, 60.0, 60.0]]
+        );
+    }
+
+    #[test]
+    fn decode_skips_nan_scores_and_boxes() {
+        let xywh = [50.0, 50.0, 20.0, 20.0];
+        let raw = raw_output(&[
+            // A NaN score loses to any real one for the same anchor.
+            (0, xywh, 0, f32::NAN),
+            (0, xywh, 1, 0.3),
+            // Only NaN scores: nothing to keep.
+            (1, xywh, 0, f32::NAN),
+            // Confident, but the box is unusable.
+            (2, [f32::NAN, 50.0, 20.0, 20.0], 0, 0.9),
+        ]);
+        let decoded = decode_and_filter(&raw, &identity(), 0.25);
+        assert_eq!(decoded, vec![det([40.0, 40.0, 60.0, 60.0], 1, 0.3)]);
+    }
+
+    #[test]
+    fn decoded_duplicates_collapse_to_one_box_per_object() {
+        // Neighbouring anchors firing on the same person and the same chair.
+        let raw = raw_output(&[
+            (100, [200.0, 300.0, 100.0, 300.0], 0, 0.85),
+            (101, [204.0, 302.0, 100.0, 296.0], 0, 0.9),
+            (5000, [198.0, 298.0, 104.0, 300.0], 0, 0.6),
+            (200, [450.0, 400.0, 120.0, 120.0], 56, 0.5),
+            (201, [455.0, 402.0, 118.0, 120.0], 56, 0.45),
+        ]);
+        let decoded = decode_and_filter(&raw, &identity(), 0.25);
+        let kept = non_maximum_suppression(decoded, &config(Suppression::PerClass));
+        let summary: Vec<(usize, f32)> = kept.iter().map(|d| (d.class_idx, d.confidence)).collect();
+        assert_eq!(summary, vec![(0, 0.9), (56, 0.5)]);
+    }
+
+    #[test]
+    fn iou_of_identical_boxes_is_one() {
+        let a = [10.0, 20.0, 110.0, 70.0];
+        assert_eq!(intersection_over_union(&a, &a), 1.0);
+    }
+
+    #[test]
+    fn iou_of_disjoint_and_touching_boxes_is_zero() {
+        let a = [0.0, 0.0, 100.0, 100.0];
+        assert_eq!(
+            intersection_over_union(&a, &[200.0, 200.0, 300.0, 300.0]),
+            0.0
+        );
+        assert_eq!(
+            intersection_over_union(&a, &[100.0, 0.0, 200.0, 100.0]),
+            0.0
+        );
+    }
+
+    #[test]
+    fn iou_is_symmetric_for_partial_overlap() {
+        let a = [0.0, 0.0, 100.0, 100.0];
+        let inner = [0.0, 0.0, 50.0, 50.0];
+        assert_eq!(intersection_over_union(&a, &inner), 0.25);
+        assert_eq!(intersection_over_union(&inner, &a), 0.25);
+    }
+
+    #[test]
+    fn iou_with_zero_area_boxes_is_zero() {
+        let a = [0.0, 0.0, 100.0, 100.0];
+        let point = [50.0, 50.0, 50.0, 50.0];
+        let line = [0.0, 50.0, 100.0, 50.0];
+        assert_eq!(intersection_over_union(&a, &point), 0.0);
+        assert_eq!(intersection_over_union(&a, &line), 0.0);
+        assert_eq!(intersection_over_union(&point, &point), 0.0);
+    }
+
+    #[test]
+    fn iou_with_nan_coordinates_is_zero() {
+        let a = [0.0, 0.0, 100.0, 100.0];
+        let broken = [f32::NAN, 0.0, 100.0, 100.0];
+        assert_eq!(intersection_over_union(&a, &broken), 0.0);
+        assert_eq!(intersection_over_union(&broken, &broken), 0.0);
+    }
+
+    #[test]
+    fn every_strategy_drops_nan_confidences() {
+        let detections = vec![
+            det([0.0, 0.0, 100.0, 100.0], 0, f32::NAN),
+            det([0.0, 0.0, 100.0, 100.0], 0, 0.9),
+            det([200.0, 200.0, 300.0, 300.0], 1, 0.5),
+        ];
+        for suppression in Suppression::ALL {
+            let kept = non_maximum_suppression(detections.clone(), &config(suppression));
+            let confidences: Vec<f32> = kept.iter().map(|d| d.confidence).collect();
+            assert_eq!(confidences, vec![0.9, 0.5], "{suppression}");
+        }
+    }
+
+    /// Two near-identical boxes of different classes plus one disjoint box.
+    fn cup_and_bowl() -> Vec<Detection> {
+        vec![
+            det([0.0, 0.0, 100.0, 100.0], 41, 0.8),
+            det([5.0, 5.0, 100.0, 100.0], 45, 0.6),
+            det([200.0, 200.0, 300.0, 300.0], 41, 0.5),
+        ]
+    }
+
+    #[test]
+    fn per_class_keeps_overlapping_boxes_of_different_classes() {
+        let kept = non_maximum_suppression(cup_and_bowl(), &config(Suppression::PerClass));
+        let classes: Vec<usize> = kept.iter().map(|d| d.class_idx).collect();
+        assert_eq!(classes, vec![41, 45, 41]);
+    }
+
+    #[test]
+    fn per_class_suppresses_overlapping_boxes_of_same_class() {
+        let detections = vec![
+            det([0.0, 0.0, 100.0, 100.0], 0, 0.9),
+            det([5.0, 5.0, 100.0, 100.0], 0, 0.7),
+        ];
+        let kept = non_maximum_suppression(detections, &config(Suppression::PerClass));
+        assert_eq!(kept, vec![det([0.0, 0.0, 100.0, 100.0], 0, 0.9)]);
+    }
+
+    #[test]
+    fn class_agnostic_suppresses_overlapping_boxes_of_any_class() {
+        let kept = non_maximum_suppression(cup_and_bowl(), &config(Suppression::ClassAgnostic));
+        let confidences: Vec<f32> = kept.iter().map(|d| d.confidence).collect();
+        assert_eq!(confidences, vec![0.8, 0.5]);
+    }
+
+    #[test]
+    fn hard_suppression_keeps_boxes_below_iou_threshold() {
+        // IoU of these two boxes is 1/3, below the default 0.45.
+        let detections = vec![
+            det([0.0, 0.0, 100.0, 100.0], 0, 0.9),
+            det([50.0, 0.0, 150.0, 100.0], 0, 0.7),
+        ];
+        let kept = non_maximum_suppression(detections, &config(Suppression::ClassAgnostic));
+        assert_eq!(kept.len(), 2);
+    }
+
+    #[test]
+    fn soft_decays_overlapping_boxes_and_leaves_disjoint_ones() {
+        let detections = vec![
+            det([0.0, 0.0, 100.0, 100.0], 0, 0.9),
+            det([50.0, 0.0, 150.0, 100.0], 1, 0.8),
+            det([200.0, 200.0, 300.0, 300.0], 2, 0.3),
+        ];
+        let kept = non_maximum_suppression(detections, &config(Suppression::Soft));
+
+        assert_eq!(kept.len(), 3);
+        assert_eq!(kept[0].confidence, 0.9);
+        let iou: f32 = 1.0 / 3.0;
+        let expected = 0.8 * (-(iou * iou) / SOFT_NMS_SIGMA).exp();
+        assert!((kept[1].confidence - expected).abs() < 1e-6);
+        assert_eq!(kept[2].confidence, 0.3);
+    }
+
+    #[test]
+    fn soft_drops_boxes_decayed_below_conf_threshold() {
+        // Identical boxes: decay factor exp(-1 / 0.5) ~= 0.135, so 0.6 -> ~0.08 < 0.25.
+        let detections = vec![
+            det([0.0, 0.0, 100.0, 100.0], 0, 0.9),
+            det([0.0, 0.0, 100.0, 100.0], 1, 0.6),
+        ];
+        let kept = non_maximum_suppression(detections, &config(Suppression::Soft));
+        assert_eq!(kept, vec![det([0.0, 0.0, 100.0, 100.0], 0, 0.9)]);
+    }
+
+    #[test]
+    fn every_strategy_respects_max_detections() {
+        let detections: Vec<Detection> = [0.9, 0.8, 0.7, 0.6, 0.5]
+            .into_iter()
+            .enumerate()
+            .map(|(i, confidence)| {
+                let x = i as f32 * 200.0;
+                det([x, 0.0, x + 100.0, 100.0], 0, confidence)
+            })
+            .collect();
+        for suppression in Suppression::ALL {
+            let cfg = DetectionConfig {
+                max_detections: 2,
+                suppression,
+                ..DetectionConfig::default()
+            };
+            let kept = non_maximum_suppression(detections.clone(), &cfg);
+            let confidences: Vec<f32> = kept.iter().map(|d| d.confidence).collect();
+            assert_eq!(confidences, vec![0.9, 0.8], "{suppression}");
+
+            let none = DetectionConfig {
+                max_detections: 0,
+                ..cfg
+            };
+            assert!(
+                non_maximum_suppression(detections.clone(), &none).is_empty(),
+                "{suppression}"
+            );
+        }
+    }
+
+    #[test]
+    fn clamped_config_stays_in_settings_ranges() {
+        let config = DetectionConfig {
+            conf_threshold: f32::NAN,
+            iou_threshold: 3.0,
+            max_detections: 0,
+            suppression: Suppression::Soft,
+        }
+        .clamped();
+        assert_eq!(
+            config.conf_threshold,
+            DetectionConfig::default().conf_threshold
+        );
+        assert_eq!(config.iou_threshold, 0.95);
+        assert_eq!(config.max_detections, 1);
+        assert_eq!(config.suppression, Suppression::Soft);
+
+        let infinite = DetectionConfig {
+            iou_threshold: f32::NEG_INFINITY,
+            max_detections: 1000,
+            ..DetectionConfig::default()
+        }
+        .clamped();
+        assert_eq!(
+            infinite.iou_threshold,
+            DetectionConfig::default().iou_threshold
+        );
+        assert_eq!(infinite.max_detections, 20);
+        assert_eq!(
+            DetectionConfig::default().clamped(),
+            DetectionConfig::default()
+        );
+    }
+
+    #[test]
+    fn suppression_round_trips_through_strings() {
+        for suppression in Suppression::ALL {
+            assert_eq!(suppression.to_string().parse(), Ok(suppression));
+        }
+        assert!("greedy".parse::<Suppression>().is_err());
+    }
+
+    /// The label tables are checked against `NUM_CLASSES` at build time; this
+    /// checks `NUM_CLASSES` and `NUM_ANCHORS` against the model itself.
+    #[test]
+    fn model_output_matches_label_tables() {
+        let device = Default::default();
+        let input = Tensor::<Backend, 4>::zeros([1, 3, 640, 640], &device);
+        let [batch, rows, anchors] = Model::<Backend>::from_embedded(&device)
+            .forward(input)
+            .dims();
+
+        assert_eq!(batch, 1);
+        assert_eq!(rows - 4, NUM_CLASSES);
+        assert_eq!(anchors, NUM_ANCHORS);
+        assert_eq!(labels_yolo::LABELS_YOLO.len(), NUM_CLASSES);
+        assert_eq!(labels_yolo_norsk::LABELS_YOLO_NORSK.len(), NUM_CLASSES);
+    }
+}
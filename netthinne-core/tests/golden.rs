@@ -0,0 +1,156 @@
+//! End-to-end check of both embedded models on photos with known contents.
+//!
+//! Each row of `tests/golden/cases.tsv` names a photo in `tests/golden/` with
+//! the labels it must produce, in English and optionally Norwegian. Inference is slow in debug builds, so
+//! these run only on request:
+//! `cargo test -p netthinne-core --release -- --ignored`.
+
+use std::fs;
+use std::path::Path;
+
+use netthinne_core::image_utils::decode_image;
+use netthinne_core::ml::{process_image, DetectedObject, DetectionConfig};
+
+struct Case {
+    photo: String,
+    yolo: Expect,
+    imagenet: Expect,
+}
+
+/// One label column of `cases.tsv`.
+enum Expect {
+    /// `*`: any result passes.
+    Any,
+    /// `-`: YOLO finds nothing and the whole photo is classified.
+    Nothing,
+    /// `en` or `en=no`: a result with this English label, and this Norwegian
+    /// noun when one is given.
+    Label { en: String, no: Option<String> },
+}
+
+impl Expect {
+    fn parse(column: &str) -> Self {
+        match column {
+            "*" => Expect::Any,
+            "-" => Expect::Nothing,
+            label => {
+                let (en, no) = match label.split_once('=') {
+                    Some((en, no)) => (en, Some(no.to_string())),
+                    None => (label, None),
+                };
+                Expect::Label {
+                    en: en.to_string(),
+                    no,
+                }
+            }
+        }
+    }
+
+    /// Whether `found`, the (English, Norwegian) labels a model gave, satisfy
+    /// this column.
+    fn matches(&self, found: &[(&str, &str)]) -> bool {
+        match self {
+            Expect::Any => true,
+            Expect::Nothing => found.is_empty(),
+            Expect::Label { en, no } => found
+                .iter()
+                .any(|&(e, n)| e == en && no.as_ref().is_none_or(|no| n == no)),
+        }
+    }
+}
+
+impl std::fmt::Display for Expect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expect::Any => f.write_str("anything"),
+            Expect::Nothing => f.write_str("nothing"),
+            Expect::Label { en, no: None } => write!(f, "{en:?}"),
+            Expect::Label { en, no: Some(no) } => write!(f, "{en:?} ({no:?})"),
+        }
+    }
+}
+
+fn cases(dir: &Path) -> Vec<Case> {
+    let text = fs::read_to_string(dir.join("cases.tsv")).expect("tests/golden/cases.tsv");
+    text.lines()
+        .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+        .map(|line| {
+            let cols: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let [photo, yolo, imagenet] = cols[..] else {
+                panic!("cases.tsv: expected 3 tab-separated columns in {line:?}");
+            };
+            Case {
+                photo: photo.to_string(),
+                yolo: Expect::parse(yolo),
+                imagenet: Expect::parse(imagenet),
+            }
+        })
+        .collect()
+}
+
+/// Why `objects` do not match `case`, if they do not.
+fn mismatch(case: &Case, objects: &[DetectedObject]) -> Option<String> {
+    let yolo: Vec<(&str, &str)> = objects
+        .iter()
+        .filter_map(|o| o.yolo.as_ref())
+        .map(|l| (l.label_en.as_str(), l.label_no.as_str()))
+        .collect();
+    if !case.yolo.matches(&yolo) {
+        return Some(format!("YOLO gave {yolo:?}, expected {}", case.yolo));
+    }
+
+    let imagenet: Vec<(&str, &str)> = objects
+        .iter()
+        .flat_map(|o| o.inet_predictions.iter())
+        .map(|p| (p.label_en.as_str(), p.label_no.as_str()))
+        .collect();
+    if !case.imagenet.matches(&imagenet) {
+        return Some(format!(
+            "ImageNet top 5 was {imagenet:?}, expected {}",
+            case.imagenet
+        ));
+    }
+
+    let untranslated = objects
+        .iter()
+        .flat_map(|o| {
+            let yolo = o.yolo.iter().map(|l| (&l.label_en, &l.label_no));
+            let inet = o
+                .inet_predictions
+                .iter()
+                .map(|p| (&p.label_en, &p.label_no));
+            yolo.chain(inet)
+        })
+        .find(|(_, no)| no.as_str() == "ukjent");
+    untranslated.map(|(en, _)| format!("{en:?} has no Norwegian label"))
+}
+
+#[test]
+#[ignore = "runs both models; use --release -- --ignored"]
+fn photos_get_their_expected_labels() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let cases = cases(&dir);
+    assert!(
+        !cases.is_empty(),
+        "no photos listed in tests/golden/cases.tsv"
+    );
+
+    let failures: Vec<String> = cases
+        .iter()
+        .filter_map(|case| {
+            let bytes = fs::read(dir.join(&case.photo)).expect(&case.photo);
+            let frame = decode_image(&bytes).expect(&case.photo);
+            let objects = process_image(
+                &frame.pixels,
+                frame.width,
+                frame.height,
+                &DetectionConfig::default(),
+                |_| {},
+            )
+            .expect(&case.photo);
+            mismatch(case, &objects).map(|reason| format!("{}: {reason}", case.photo))
+        })
+        .collect();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
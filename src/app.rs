@@ -1,14 +1,45 @@
+use std::collections::HashSet;
+
+use dioxus::html::{FileData, HasFileData};
 use dioxus::prelude::*;
 
-use crate::camera;
-use crate::ml;
+use netthinne_core::image_utils::ScanImages;
+use netthinne_core::ml::{self, DetectedObject, DetectionConfig, Inflection, Stage, Suppression};
+
+use crate::camera::{self, CameraCapabilities, CameraChoice};
+use crate::deck::{self, Card, Direction, Grade};
+use crate::error::NetthinneError;
+use crate::export;
+use crate::history::{self, Scan};
+use crate::live;
+use crate::settings;
+use crate::speech;
 use crate::state::{AppState, Screen};
+use crate::worker;
 
 const VIDEO_ID: &str = "camera-preview";
+const OVERLAY_ID: &str = "live-overlay";
+/// Below this top-1 probability the ImageNet alternatives are listed.
+const UNSURE_PROBABILITY: f32 = 0.5;
 
 #[component]
 pub fn App() -> Element {
-    let state = use_signal(AppState::default);
+    let mut state = use_signal(|| AppState {
+        detection_config: settings::load_detection_config(),
+        camera_choice: settings::load_camera_choice(),
+        ..AppState::default()
+    });
+    use_hook(speech::preload_voices);
+
+    use_future(move || async move {
+        // `worker::warm_up` gives up after its timeouts, so this always ends.
+        if let Err(e) = worker::warm_up().await {
+            log::error!("Worker warm-up failed, loading models on main thread: {e}");
+            state.write().worker_failed = true;
+            ml::warm_up();
+        }
+        state.write().models_ready = true;
+    });
 
     rsx! {
         document::Stylesheet { href: asset!("/assets/main.css") }
@@ -27,6 +58,9 @@ pub fn App() -> Element {
                 Screen::Camera => rsx! { CameraScreen { state } },
                 Screen::Processing => rsx! { ProcessingScreen { state } },
                 Screen::Result => rsx! { ResultScreen { state } },
+                Screen::Settings => rsx! { SettingsScreen { state } },
+                Screen::History => rsx! { HistoryScreen { state } },
+                Screen::Review => rsx! { ReviewScreen { state } },
             }
         }
     }
@@ -34,15 +68,122 @@ pub fn App() -> Element {
 
 #[component]
 fn CameraScreen(state: Signal<AppState>) -> Element {
+    let mut caps = use_signal(CameraCapabilities::default);
+    let mut zoom = use_signal(|| 1.0_f64);
+    let mut torch = use_signal(|| false);
+    // (finger distance, zoom) when the current pinch started.
+    let mut pinch = use_signal(|| None::<(f64, f64)>);
+    let mut focus_point = use_signal(|| None::<(f64, f64)>);
+    let mut live = use_signal(|| false);
+    let mut due = use_signal(|| 0);
+
     use_future(move || async move {
-        if let Err(e) = camera::start_camera(VIDEO_ID).await {
+        match deck::load_cards().await {
+            Ok(cards) => due.set(deck::due_cards(&cards, js_sys::Date::now()).len()),
+            Err(e) => log::warn!("Loading the deck failed: {e}"),
+        }
+    });
+
+    // Samples preview frames while live mode is on. Only the YOLO stage runs,
+    // in the worker; the shutter still runs the full pipeline on a still.
+    use_future(move || async move {
+        loop {
+            live::sleep(live::FRAME_INTERVAL_MS).await;
+            if !live() || !state.read().models_ready {
+                continue;
+            }
+            let (pixels, w, h) = match camera::capture_preview(VIDEO_ID, live::FRAME_MAX_SIDE) {
+                Ok(frame) => frame,
+                Err(NetthinneError::VideoNotReady) => continue,
+                Err(e) => {
+                    log::warn!("Live capture failed: {e}");
+                    continue;
+                }
+            };
+            let config = state.read().detection_config;
+            match worker::detect_objects(&pixels, w, h, &config).await {
+                // Live may have been switched off while the worker was busy.
+                Ok(boxes) if live() => {
+                    if let Err(e) = live::draw_boxes(OVERLAY_ID, w, h, &boxes) {
+                        log::warn!("Drawing live boxes failed: {e}");
+                    }
+                }
+                Ok(_) => {}
+                Err(e @ NetthinneError::Worker(_)) => {
+                    // Running YOLO on the main thread every frame would freeze the UI.
+                    log::error!("Live detection needs the worker, turning it off: {e}");
+                    state.write().worker_failed = true;
+                    live.set(false);
+                    let _ = live::clear(OVERLAY_ID);
+                }
+                Err(e) => log::warn!("Live detection failed: {e}"),
+            }
+        }
+    });
+
+    let mut camera_task = use_future(move || async move {
+        let choice = state.read().camera_choice.clone();
+        let mut result = camera::start_camera(VIDEO_ID, &choice).await;
+        if let (CameraChoice::Device(_), Err(NetthinneError::NoCamera)) = (&choice, &result) {
+            // The remembered device is gone; fall back to the default camera.
+            if let Err(e) = settings::save_camera_choice(&CameraChoice::default()) {
+                log::error!("Saving camera choice failed: {e}");
+            }
+            state.write().camera_choice = CameraChoice::default();
+            result = camera::start_camera(VIDEO_ID, &CameraChoice::default()).await;
+        }
+        if let Err(e) = result {
             log::error!("Camera error: {}", e);
             state.write().error = Some(e);
+            return;
+        }
+        match camera::capabilities(VIDEO_ID) {
+            Ok(c) => {
+                zoom.set(c.zoom.map_or(1.0, |z| z.min.max(1.0).min(z.max)));
+                caps.set(c);
+            }
+            Err(e) => log::error!("Reading camera capabilities failed: {e}"),
+        }
+        torch.set(false);
+        focus_point.set(None);
+        match camera::list_cameras().await {
+            Ok(cameras) => state.write().cameras = cameras,
+            Err(e) => log::error!("Listing cameras failed: {e}"),
         }
     });
 
+    let mut switch_camera = move |choice: CameraChoice| {
+        let _ = camera::stop_camera(VIDEO_ID);
+        if let Err(e) = settings::save_camera_choice(&choice) {
+            log::error!("Saving camera choice failed: {e}");
+        }
+        {
+            let mut s = state.write();
+            s.camera_choice = choice;
+            s.error = None;
+        }
+        camera_task.restart();
+    };
+
+    let cameras = state.read().cameras.clone();
+    let choice = state.read().camera_choice.clone();
+
+    let models_ready = state.read().models_ready;
+    let worker_failed = state.read().worker_failed;
+
     rsx! {
-        div { class: "camera-screen",
+        div {
+            class: "camera-screen",
+            ondragover: move |evt| evt.prevent_default(),
+            ondrop: move |evt| async move {
+                evt.prevent_default();
+                if !models_ready {
+                    return;
+                }
+                if let Some(file) = evt.files().into_iter().next() {
+                    import_image(state, file).await;
+                }
+            },
             h1 { class: "app-title", "Netthinne" }
             div { class: "camera-viewport",
                 video {
@@ -50,11 +191,69 @@ fn CameraScreen(state: Signal<AppState>) -> Element {
                     autoplay: true,
                     playsinline: true,
                     class: "camera-preview",
+                    onclick: move |evt| {
+                        let Some(mode) = caps.read().focus_mode.clone() else {
+                            return;
+                        };
+                        let p = evt.element_coordinates();
+                        let Some((x, y)) = camera::preview_point(VIDEO_ID, p.x, p.y) else {
+                            return;
+                        };
+                        focus_point.set(Some((x, y)));
+                        spawn(async move {
+                            if let Err(e) = camera::focus_at(VIDEO_ID, x, y, &mode).await {
+                                log::warn!("Focus failed: {e}");
+                            }
+                        });
+                    },
+                    ontouchmove: move |evt| {
+                        let Some(range) = caps.read().zoom else {
+                            return;
+                        };
+                        let touches = evt.touches();
+                        if touches.len() != 2 {
+                            pinch.set(None);
+                            return;
+                        }
+                        let a = touches[0].client_coordinates();
+                        let b = touches[1].client_coordinates();
+                        let distance = (a.x - b.x).hypot(a.y - b.y);
+                        let Some((start_distance, start_zoom)) = pinch() else {
+                            pinch.set(Some((distance, zoom())));
+                            return;
+                        };
+                        let mut target = start_zoom * distance / start_distance.max(1.0);
+                        // Some cameras report a zero step; snapping to it would yield NaN.
+                        if range.step > 0.0 {
+                            target = (target / range.step).round() * range.step;
+                        }
+                        let target = target.clamp(range.min, range.max);
+                        if target != zoom() {
+                            zoom.set(target);
+                            spawn(async move {
+                                if let Err(e) = camera::set_zoom(VIDEO_ID, target).await {
+                                    log::warn!("Zoom failed: {e}");
+                                }
+                            });
+                        }
+                    },
+                    ontouchend: move |_| pinch.set(None),
+                }
+                canvas { id: OVERLAY_ID, class: "live-overlay", hidden: !live() }
+                if let Some((x, y)) = focus_point() {
+                    div {
+                        class: "focus-ring",
+                        style: "left: {x * 100.0}%; top: {y * 100.0}%;",
+                    }
+                }
+                if caps.read().zoom.is_some() {
+                    span { class: "zoom-level", "{zoom():.1}×" }
                 }
                 button {
                     class: "capture-btn",
-                    onclick: move |_| {
-                        match camera::capture_frame(VIDEO_ID) {
+                    disabled: !models_ready,
+                    onclick: move |_| async move {
+                        match camera::take_photo(VIDEO_ID).await {
                             Ok((pixels, w, h)) => {
                                 let _ = camera::stop_camera(VIDEO_ID);
                                 let mut s = state.write();
@@ -69,22 +268,202 @@ fn CameraScreen(state: Signal<AppState>) -> Element {
                     },
                 }
             }
-            if let Some(ref err) = state.read().error {
-                p { class: "error-text", "{err}" }
+            div { class: "camera-controls",
+                button {
+                    class: "settings-btn",
+                    onclick: move |_| {
+                        let next = if state.read().camera_choice == CameraChoice::Front {
+                            CameraChoice::Back
+                        } else {
+                            CameraChoice::Front
+                        };
+                        switch_camera(next);
+                    },
+                    "Flip"
+                }
+                button {
+                    class: if live() { "settings-btn active" } else { "settings-btn" },
+                    // Live detection runs only in the worker.
+                    disabled: !models_ready || worker_failed,
+                    onclick: move |_| {
+                        let on = !live();
+                        live.set(on);
+                        if !on {
+                            let _ = live::clear(OVERLAY_ID);
+                        }
+                    },
+                    "Live"
+                }
+                label {
+                    class: if models_ready { "settings-btn" } else { "settings-btn disabled" },
+                    "Open image"
+                    input {
+                        r#type: "file",
+                        accept: "image/*,.heic,.heif",
+                        hidden: true,
+                        disabled: !models_ready,
+                        onchange: move |evt| async move {
+                            if let Some(file) = evt.files().into_iter().next() {
+                                import_image(state, file).await;
+                            }
+                        },
+                    }
+                }
+                if caps.read().torch {
+                    button {
+                        class: if torch() { "settings-btn active" } else { "settings-btn" },
+                        onclick: move |_| async move {
+                            let on = !torch();
+                            match camera::set_torch(VIDEO_ID, on).await {
+                                Ok(()) => torch.set(on),
+                                Err(e) => log::warn!("Torch failed: {e}"),
+                            }
+                        },
+                        "Light"
+                    }
+                }
+                if cameras.len() > 1 {
+                    select {
+                        class: "setting-select camera-select",
+                        value: "{choice}",
+                        onchange: move |evt| {
+                            if let Ok(choice) = evt.value().parse() {
+                                switch_camera(choice);
+                            }
+                        },
+                        option {
+                            value: "{CameraChoice::Back}",
+                            selected: choice == CameraChoice::Back,
+                            "Back camera"
+                        }
+                        option {
+                            value: "{CameraChoice::Front}",
+                            selected: choice == CameraChoice::Front,
+                            "Front camera"
+                        }
+                        for (i, cam) in cameras.iter().enumerate() {
+                            option {
+                                key: "{cam.device_id}",
+                                value: "{CameraChoice::Device(cam.device_id.clone())}",
+                                selected: choice == CameraChoice::Device(cam.device_id.clone()),
+                                if cam.label.is_empty() {
+                                    "Camera {i + 1}"
+                                } else {
+                                    "{cam.label}"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if !state.read().models_ready {
+                p { class: "model-status", "Loading models..." }
+            } else if worker_failed {
+                p { class: "model-status",
+                    "Background recognition is unavailable; the page may pause while it works."
+                }
+            }
+            if let Some(err) = state.read().error.clone() {
+                ErrorPanel {
+                    error: err,
+                    on_retry: move |_| {
+                        state.write().error = None;
+                        camera_task.restart();
+                    },
+                }
+            }
+            div { class: "camera-controls",
+                button {
+                    class: "settings-btn",
+                    onclick: move |_| {
+                        let _ = camera::stop_camera(VIDEO_ID);
+                        state.write().screen = Screen::Review;
+                    },
+                    if due() > 0 {
+                        "Review ({due})"
+                    } else {
+                        "Review"
+                    }
+                }
+                button {
+                    class: "settings-btn",
+                    onclick: move |_| {
+                        let _ = camera::stop_camera(VIDEO_ID);
+                        state.write().screen = Screen::History;
+                    },
+                    "History"
+                }
+                button {
+                    class: "settings-btn",
+                    onclick: move |_| {
+                        let _ = camera::stop_camera(VIDEO_ID);
+                        state.write().screen = Screen::Settings;
+                    },
+                    "Settings"
+                }
             }
         }
     }
 }
 
+/// Decodes a picked or dropped photo and hands it to the same pipeline as a capture.
+async fn import_image(mut state: Signal<AppState>, file: FileData) {
+    let result = match file.read_bytes().await {
+        Ok(bytes) => camera::decode_image_file(&bytes).await,
+        Err(e) => Err(NetthinneError::Preprocessing(format!("reading file: {e}"))),
+    };
+    match result {
+        Ok((pixels, w, h)) => {
+            let _ = camera::stop_camera(VIDEO_ID);
+            let mut s = state.write();
+            s.error = None;
+            s.captured_pixels = Some((pixels, w, h));
+            s.screen = Screen::Processing;
+        }
+        Err(e) => {
+            log::error!("Opening {} failed: {e}", file.name());
+            state.write().error = Some(e);
+        }
+    }
+}
+
 #[component]
 fn ProcessingScreen(state: Signal<AppState>) -> Element {
-    use_future(move || async move {
+    let mut stage = use_signal(|| None::<Stage>);
+
+    let mut task = use_future(move || async move {
         let data = state.read().captured_pixels.clone();
+        let config = state.read().detection_config;
         match data {
             Some((pixels, w, h)) => {
-                let detections = ml::process_image(&pixels, w, h);
+                let on_progress = move |s: Stage| stage.set(Some(s));
+                // Without a worker, skip straight to the main thread instead of
+                // waiting for another start-up to time out.
+                let result = if state.read().worker_failed {
+                    worker::process_scan(&pixels, w, h, &config, on_progress).map_err(Into::into)
+                } else {
+                    match worker::process_image(&pixels, w, h, &config, on_progress).await {
+                        Err(NetthinneError::Worker(e)) => {
+                            log::error!("Worker inference failed, running on main thread: {e}");
+                            state.write().worker_failed = true;
+                            worker::process_scan(&pixels, w, h, &config, on_progress)
+                                .map_err(Into::into)
+                        }
+                        other => other,
+                    }
+                };
+                let (detections, images) = match result {
+                    Ok(scan) => scan,
+                    Err(e) => {
+                        log::error!("Processing failed: {e}");
+                        state.write().error = Some(e);
+                        return;
+                    }
+                };
+                save_to_history(&images, w, h, &detections);
                 let mut s = state.write();
                 s.detections = detections;
+                s.captured_image = Some((images.frame_url, w, h));
                 s.captured_pixels = None;
                 s.screen = Screen::Result;
             }
@@ -95,39 +474,178 @@ fn ProcessingScreen(state: Signal<AppState>) -> Element {
         }
     });
 
+    if let Some(err) = state.read().error.clone() {
+        return rsx! {
+            div { class: "processing-screen",
+                ErrorPanel {
+                    error: err,
+                    on_retry: move |_| {
+                        state.write().error = None;
+                        stage.set(None);
+                        task.restart();
+                    },
+                }
+                button {
+                    class: "new-scan-btn",
+                    onclick: move |_| {
+                        state.write().reset();
+                    },
+                    "New Scan"
+                }
+            }
+        };
+    }
+
+    let (text, fraction) = match stage() {
+        None => ("Analyzing...".to_string(), 0.0),
+        Some(Stage::Detecting) => ("Finding objects...".to_string(), 0.0),
+        Some(s @ Stage::Classifying { done, total }) => (
+            format!("Identifying {} of {}...", done + 1, total),
+            s.fraction(),
+        ),
+    };
+
     rsx! {
         div { class: "processing-screen",
-            p { class: "processing-text", "Analyzing..." }
+            p { class: "processing-text", "{text}" }
+            progress {
+                class: "processing-progress",
+                max: "1",
+                value: "{fraction}",
+            }
         }
     }
 }
 
+/// Stores the finished scan in the background; failures are only logged.
+/// The result screen keeps full-size PNG crops; history stores the small JPEGs
+/// from `images`.
+fn save_to_history(images: &ScanImages, width: u32, height: u32, detections: &[DetectedObject]) {
+    let detections = detections
+        .iter()
+        .zip(&images.crop_urls)
+        .map(|(detection, crop_url)| DetectedObject {
+            image_data_url: crop_url.clone(),
+            ..detection.clone()
+        })
+        .collect();
+    let scan = Scan {
+        id: 0,
+        timestamp: js_sys::Date::now(),
+        thumbnail_url: images.thumbnail_url.clone(),
+        width,
+        height,
+        detections,
+    };
+    spawn(async move {
+        if let Err(e) = history::save_scan(&scan).await {
+            log::error!("Saving scan to history failed: {e}");
+        }
+    });
+}
+
 #[component]
 fn ResultScreen(state: Signal<AppState>) -> Element {
     let detections = state.read().detections.clone();
+    let captured_image = state.read().captured_image.clone();
+    let mut selected = use_signal(|| None::<usize>);
+    // English labels already in the flashcard deck.
+    let mut in_deck = use_signal(HashSet::<String>::new);
+
+    use_future(move || async move {
+        match deck::load_cards().await {
+            Ok(cards) => in_deck.set(cards.into_iter().map(|c| c.label_en).collect()),
+            Err(e) => log::warn!("Loading the deck failed: {e}"),
+        }
+    });
 
     rsx! {
         div { class: "result-screen",
             h1 { class: "app-title", "Netthinne" }
+            if let Some((url, w, h)) = captured_image {
+                AnnotatedFrame {
+                    url,
+                    width: w,
+                    height: h,
+                    detections: detections.clone(),
+                    selected,
+                }
+            }
             if detections.is_empty() {
                 p { class: "no-detections", "No objects detected" }
             }
             div { class: "detections-list",
                 for (i, det) in detections.iter().enumerate() {
-                    div { class: "detection-card", key: "{i}",
+                    div {
+                        id: "{card_id(i)}",
+                        class: if selected() == Some(i) { "detection-card selected" } else { "detection-card" },
+                        key: "{i}",
+                        onclick: move |_| selected.set(Some(i)),
                         div { class: "detection-image",
                             img {
                                 src: "{det.image_data_url}",
-                                alt: "{det.yolo_label_en}",
+                                alt: "Detected object",
+                                title: "{det.bbox[0]:.0}, {det.bbox[1]:.0} – {det.bbox[2]:.0}, {det.bbox[3]:.0}",
                             }
                         }
                         div { class: "detection-labels",
-                            p { class: "section-header", "YOLO" }
-                            p { class: "label-english", "{det.yolo_label_en}" }
-                            p { class: "label-norwegian", "{det.yolo_label_no}" }
-                            p { class: "section-header mt", "ImageNet-1k" }
-                            p { class: "label-english", "{det.inet_label_en}" }
-                            p { class: "label-norwegian", "{det.inet_label_no}" }
+                            if let Some(yolo) = &det.yolo {
+                                p { class: "section-header", "YOLO" }
+                                p { class: "label-english", "{yolo.label_en}" }
+                                p { class: "label-norwegian", "{yolo.label_no}" }
+                                SpeakButtons { text: yolo.label_no.clone() }
+                                if let Some(inflection) = yolo.inflection.clone() {
+                                    InflectionTable { noun: yolo.label_no.clone(), inflection }
+                                }
+                                p { class: "label-probability", "{yolo.confidence * 100.0:.0}%" }
+                                p { class: "section-header mt", "ImageNet-1k" }
+                            } else {
+                                p { class: "section-header", "Whole image · ImageNet-1k" }
+                            }
+                            if let Some(top) = det.inet_predictions.first() {
+                                p { class: "label-english", "{top.label_en}" }
+                                p { class: "label-norwegian", "{top.label_no}" }
+                                SpeakButtons { text: top.label_no.clone() }
+                                if let Some(inflection) = top.inflection.clone() {
+                                    InflectionTable { noun: top.label_no.clone(), inflection }
+                                }
+                                p { class: "label-probability", "{top.probability * 100.0:.0}%" }
+                                if top.probability < UNSURE_PROBABILITY {
+                                    p { class: "section-header mt", "Maybe" }
+                                    for alt in det.inet_predictions.iter().skip(1) {
+                                        p { class: "label-alternative",
+                                            "{alt.label_en} / {alt.label_no} ({alt.probability * 100.0:.0}%)"
+                                        }
+                                    }
+                                }
+                            }
+                            if let Some((label_en, _)) = deck::word(det) {
+                                if in_deck.read().contains(label_en) {
+                                    p { class: "deck-status", "In deck" }
+                                } else {
+                                    button {
+                                        class: "settings-btn deck-btn",
+                                        onclick: {
+                                            let det = det.clone();
+                                            let label_en = label_en.to_string();
+                                            move |evt: MouseEvent| {
+                                                evt.stop_propagation();
+                                                let det = det.clone();
+                                                let label_en = label_en.clone();
+                                                async move {
+                                                    match deck::add_word(&det, js_sys::Date::now()).await {
+                                                        Ok(()) => {
+                                                            in_deck.write().insert(label_en);
+                                                        }
+                                                        Err(e) => log::error!("Adding to deck failed: {e}"),
+                                                    }
+                                                }
+                                            }
+                                        },
+                                        "Add to deck"
+                                    }
+                                }
+                            }
                         }
                     }
                 }
@@ -135,10 +653,521 @@ fn ResultScreen(state: Signal<AppState>) -> Element {
             button {
                 class: "new-scan-btn",
                 onclick: move |_| {
-                    *state.write() = AppState::default();
+                    state.write().reset();
                 },
                 "New Scan"
             }
         }
     }
 }
+
+#[component]
+fn HistoryScreen(state: Signal<AppState>) -> Element {
+    let mut scans = use_signal(|| None::<Vec<Scan>>);
+    let mut error = use_signal(|| None::<NetthinneError>);
+
+    let mut load = use_future(move || async move {
+        match history::list_scans().await {
+            Ok(list) => scans.set(Some(list)),
+            Err(e) => {
+                log::error!("Loading history failed: {e}");
+                error.set(Some(e));
+            }
+        }
+    });
+
+    rsx! {
+        div { class: "history-screen",
+            h1 { class: "app-title", "History" }
+            if let Some(err) = error() {
+                ErrorPanel {
+                    error: err,
+                    on_retry: move |_| {
+                        error.set(None);
+                        load.restart();
+                    },
+                }
+            }
+            match scans() {
+                None => rsx! {},
+                Some(list) if list.is_empty() => rsx! {
+                    p { class: "no-detections", "No saved scans yet" }
+                },
+                Some(list) => rsx! {
+                    div { class: "history-list",
+                        for scan in list {
+                            HistoryItem {
+                                key: "{scan.id}",
+                                scan: scan.clone(),
+                                on_open: move |scan: Scan| {
+                                    let mut s = state.write();
+                                    s.captured_image = Some((scan.thumbnail_url, scan.width, scan.height));
+                                    s.detections = scan.detections;
+                                    s.screen = Screen::Result;
+                                },
+                                on_delete: move |id: u32| async move {
+                                    match history::delete_scan(id).await {
+                                        Ok(()) => {
+                                            if let Some(list) = scans.write().as_mut() {
+                                                list.retain(|s| s.id != id);
+                                            }
+                                        }
+                                        Err(e) => {
+                                            log::error!("Deleting scan failed: {e}");
+                                            error.set(Some(e));
+                                        }
+                                    }
+                                },
+                            }
+                        }
+                    }
+                },
+            }
+            button {
+                class: "new-scan-btn",
+                onclick: move |_| {
+                    state.write().screen = Screen::Camera;
+                },
+                "Done"
+            }
+        }
+    }
+}
+
+#[component]
+fn HistoryItem(scan: Scan, on_open: EventHandler<Scan>, on_delete: EventHandler<u32>) -> Element {
+    let date: String = js_sys::Date::new(&scan.timestamp.into())
+        .to_locale_string("nb-NO", &wasm_bindgen::JsValue::UNDEFINED)
+        .into();
+    let words = scan
+        .detections
+        .iter()
+        .filter_map(|det| match &det.yolo {
+            Some(yolo) => Some(yolo.label_no.as_str()),
+            None => det.inet_predictions.first().map(|p| p.label_no.as_str()),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let id = scan.id;
+
+    rsx! {
+        div {
+            class: "history-item",
+            onclick: {
+                let scan = scan.clone();
+                move |_| on_open.call(scan.clone())
+            },
+            img {
+                class: "history-thumb",
+                src: "{scan.thumbnail_url}",
+                alt: "Saved scan",
+            }
+            div { class: "history-info",
+                p { class: "history-words", "{words}" }
+                p { class: "history-date", "{date}" }
+            }
+            button {
+                class: "delete-btn",
+                onclick: move |evt| {
+                    evt.stop_propagation();
+                    on_delete.call(id);
+                },
+                "Delete"
+            }
+        }
+    }
+}
+
+#[component]
+fn ReviewScreen(state: Signal<AppState>) -> Element {
+    let mut cards = use_signal(Vec::<Card>::new);
+    // Cards left in this session, front first.
+    let mut queue = use_signal(Vec::<Card>::new);
+    let mut revealed = use_signal(|| false);
+    let mut error = use_signal(|| None::<NetthinneError>);
+
+    let mut load = use_future(move || async move {
+        match deck::load_cards().await {
+            Ok(all) => {
+                queue.set(deck::due_cards(&all, js_sys::Date::now()));
+                cards.set(all);
+            }
+            Err(e) => {
+                log::error!("Loading the deck failed: {e}");
+                error.set(Some(e));
+            }
+        }
+    });
+
+    let review = move |grade: Grade| async move {
+        let Some(mut card) = queue.read().first().cloned() else {
+            return;
+        };
+        card.schedule.review(grade, js_sys::Date::now());
+        if let Err(e) = deck::save_card(&card).await {
+            log::error!("Saving card failed: {e}");
+            error.set(Some(e));
+            return;
+        }
+        if let Some(stored) = cards.write().iter_mut().find(|c| c.id() == card.id()) {
+            *stored = card.clone();
+        }
+        let mut q = queue.write();
+        q.remove(0);
+        // Lapsed cards come back at the end of the session.
+        if grade == Grade::Again {
+            q.push(card);
+        }
+        revealed.set(false);
+    };
+
+    let now = js_sys::Date::now();
+    let total = cards.read().len();
+    let current = queue.read().first().cloned();
+
+    rsx! {
+        div { class: "review-screen",
+            h1 { class: "app-title", "Review" }
+            p { class: "review-due",
+                for direction in Direction::ALL {
+                    span { key: "{direction.as_str()}",
+                        "{direction_label(direction)}: {deck::due_count(&cards.read(), direction, now)}"
+                    }
+                }
+            }
+            if let Some(err) = error() {
+                ErrorPanel {
+                    error: err,
+                    on_retry: move |_| {
+                        error.set(None);
+                        load.restart();
+                    },
+                }
+            }
+            if let Some(card) = current {
+                div { class: "flashcard",
+                    img { src: "{card.image_data_url}", alt: "Flashcard picture" }
+                    p { class: "section-header", "{direction_label(card.direction)}" }
+                    p { class: "flashcard-prompt", "{card.prompt()}" }
+                    if revealed() {
+                        p { class: "flashcard-answer", "{card.answer()}" }
+                        div { class: "grade-buttons",
+                            for g in Grade::ALL {
+                                button {
+                                    key: "{grade_label(g)}",
+                                    class: "settings-btn",
+                                    onclick: move |_| review(g),
+                                    "{grade_label(g)}"
+                                }
+                            }
+                        }
+                    } else {
+                        button {
+                            class: "new-scan-btn",
+                            onclick: move |_| revealed.set(true),
+                            "Show answer"
+                        }
+                    }
+                }
+            } else if total == 0 {
+                p { class: "no-detections", "Add words to the deck from your scan results" }
+            } else {
+                p { class: "no-detections", "Nothing to review right now" }
+            }
+            if total > 0 {
+                div { class: "export-buttons",
+                    button {
+                        class: "settings-btn",
+                        onclick: move |_| async move {
+                            if let Err(e) = export::export_apkg().await {
+                                log::error!("Anki export failed: {e}");
+                                error.set(Some(e));
+                            }
+                        },
+                        "Export to Anki"
+                    }
+                    button {
+                        class: "settings-btn",
+                        onclick: move |_| async move {
+                            if let Err(e) = export::export_csv().await {
+                                log::error!("CSV export failed: {e}");
+                                error.set(Some(e));
+                            }
+                        },
+                        "Export CSV"
+                    }
+                }
+            }
+            button {
+                class: "new-scan-btn",
+                onclick: move |_| {
+                    state.write().screen = Screen::Camera;
+                },
+                "Done"
+            }
+        }
+    }
+}
+
+#[component]
+fn SettingsScreen(state: Signal<AppState>) -> Element {
+    let config = state.read().detection_config;
+
+    rsx! {
+        div { class: "settings-screen",
+            h1 { class: "app-title", "Settings" }
+            label { class: "setting",
+                span { class: "section-header", "Confidence threshold" }
+                span { class: "setting-value", "{config.conf_threshold:.2}" }
+                input {
+                    r#type: "range",
+                    min: "{DetectionConfig::THRESHOLD_RANGE.start()}",
+                    max: "{DetectionConfig::THRESHOLD_RANGE.end()}",
+                    step: "0.05",
+                    value: "{config.conf_threshold}",
+                    oninput: move |evt| {
+                        if let Ok(v) = evt.value().parse() {
+                            update_config(state, |c| c.conf_threshold = v);
+                        }
+                    },
+                }
+            }
+            label { class: "setting",
+                span { class: "section-header", "Overlap (IoU) threshold" }
+                span { class: "setting-value", "{config.iou_threshold:.2}" }
+                input {
+                    r#type: "range",
+                    min: "{DetectionConfig::THRESHOLD_RANGE.start()}",
+                    max: "{DetectionConfig::THRESHOLD_RANGE.end()}",
+                    step: "0.05",
+                    value: "{config.iou_threshold}",
+                    oninput: move |evt| {
+                        if let Ok(v) = evt.value().parse() {
+                            update_config(state, |c| c.iou_threshold = v);
+                        }
+                    },
+                }
+            }
+            label { class: "setting",
+                span { class: "section-header", "Max objects" }
+                span { class: "setting-value", "{config.max_detections}" }
+                input {
+                    r#type: "range",
+                    min: "{DetectionConfig::MAX_DETECTIONS_RANGE.start()}",
+                    max: "{DetectionConfig::MAX_DETECTIONS_RANGE.end()}",
+                    step: "1",
+                    value: "{config.max_detections}",
+                    oninput: move |evt| {
+                        if let Ok(v) = evt.value().parse() {
+                            update_config(state, |c| c.max_detections = v);
+                        }
+                    },
+                }
+            }
+            label { class: "setting",
+                span { class: "section-header", "Overlap handling" }
+                select {
+                    class: "setting-select",
+                    value: "{config.suppression}",
+                    onchange: move |evt| {
+                        if let Ok(v) = evt.value().parse() {
+                            update_config(state, |c| c.suppression = v);
+                        }
+                    },
+                    for mode in Suppression::ALL {
+                        option {
+                            value: "{mode}",
+                            selected: mode == config.suppression,
+                            {suppression_label(mode)}
+                        }
+                    }
+                }
+            }
+            button {
+                class: "new-scan-btn",
+                onclick: move |_| {
+                    update_config(state, |c| *c = DetectionConfig::default());
+                },
+                "Reset"
+            }
+            button {
+                class: "new-scan-btn",
+                onclick: move |_| {
+                    state.write().screen = Screen::Camera;
+                },
+                "Done"
+            }
+        }
+    }
+}
+
+fn direction_label(direction: Direction) -> &'static str {
+    match direction {
+        Direction::EnglishToNorwegian => "English → Norwegian",
+        Direction::NorwegianToEnglish => "Norwegian → English",
+    }
+}
+
+fn grade_label(grade: Grade) -> &'static str {
+    match grade {
+        Grade::Again => "Again",
+        Grade::Hard => "Hard",
+        Grade::Good => "Good",
+        Grade::Easy => "Easy",
+    }
+}
+
+fn suppression_label(mode: Suppression) -> &'static str {
+    match mode {
+        Suppression::PerClass => "Per class",
+        Suppression::ClassAgnostic => "Any class",
+        Suppression::Soft => "Soft (Gaussian)",
+    }
+}
+
+/// Applies a change to the detection settings and persists them.
+fn update_config(mut state: Signal<AppState>, f: impl FnOnce(&mut DetectionConfig)) {
+    let mut s = state.write();
+    f(&mut s.detection_config);
+    if let Err(e) = settings::save_detection_config(&s.detection_config) {
+        log::error!("Saving settings failed: {e}");
+    }
+}
+
+/// Error message in English and Norwegian with a retry action.
+#[component]
+fn ErrorPanel(error: NetthinneError, on_retry: EventHandler<()>) -> Element {
+    rsx! {
+        div { class: "error-panel",
+            p { class: "error-text", "{error}" }
+            p { class: "error-text-norwegian", "{error.norwegian()}" }
+            button {
+                class: "retry-btn",
+                onclick: move |_| on_retry.call(()),
+                "Retry"
+            }
+        }
+    }
+}
+
+/// Reads a Norwegian word aloud, at normal or slow speed.
+#[component]
+fn SpeakButtons(text: String) -> Element {
+    let error = use_signal(|| None::<NetthinneError>);
+    let text_slow = text.clone();
+
+    rsx! {
+        div { class: "speak-buttons",
+            button {
+                class: "settings-btn speak-btn",
+                onclick: move |evt: MouseEvent| {
+                    evt.stop_propagation();
+                    say(text.clone(), false, error)
+                },
+                "Listen"
+            }
+            button {
+                class: "settings-btn speak-btn",
+                onclick: move |evt: MouseEvent| {
+                    evt.stop_propagation();
+                    say(text_slow.clone(), true, error)
+                },
+                "Slow"
+            }
+        }
+        if let Some(err) = error() {
+            p { class: "speech-error", "{err} · {err.norwegian()}" }
+        }
+    }
+}
+
+fn say(text: String, slow: bool, mut error: Signal<Option<NetthinneError>>) {
+    match speech::speak(&text, slow) {
+        Ok(()) => error.set(None),
+        Err(e) => {
+            log::warn!("Pronunciation failed: {e}");
+            error.set(Some(e));
+        }
+    }
+}
+
+/// Singular and plural, indefinite and definite forms of a Norwegian noun.
+#[component]
+fn InflectionTable(noun: String, inflection: Inflection) -> Element {
+    let missing = "–".to_string();
+    let plural = inflection.plural.clone().unwrap_or_else(|| missing.clone());
+    let definite_plural = inflection.definite_plural.clone().unwrap_or(missing);
+
+    rsx! {
+        table { class: "inflection-table",
+            caption { "{inflection.gender.name_no()}" }
+            thead {
+                tr {
+                    th {}
+                    th { "entall" }
+                    th { "flertall" }
+                }
+            }
+            tbody {
+                tr {
+                    th { "ubestemt" }
+                    td { "{inflection.gender.article()} {noun}" }
+                    td { "{plural}" }
+                }
+                tr {
+                    th { "bestemt" }
+                    td { "{inflection.definite}" }
+                    td { "{definite_plural}" }
+                }
+            }
+        }
+    }
+}
+
+/// Captured frame with a labeled, tappable box per detection.
+#[component]
+fn AnnotatedFrame(
+    url: String,
+    width: u32,
+    height: u32,
+    detections: Vec<DetectedObject>,
+    selected: Signal<Option<usize>>,
+) -> Element {
+    let w = width as f32;
+    let h = height as f32;
+
+    rsx! {
+        div { class: "annotated-frame",
+            img { src: "{url}", alt: "Captured photo" }
+            for (i, det, yolo) in detections
+                .iter()
+                .enumerate()
+                .filter_map(|(i, det)| det.yolo.as_ref().map(|yolo| (i, det, yolo)))
+            {
+                div {
+                    key: "{i}",
+                    class: if selected() == Some(i) { "bbox selected" } else { "bbox" },
+                    style: "left: {det.bbox[0] / w * 100.0}%; top: {det.bbox[1] / h * 100.0}%; width: {(det.bbox[2] - det.bbox[0]) / w * 100.0}%; height: {(det.bbox[3] - det.bbox[1]) / h * 100.0}%;",
+                    onclick: move |_| {
+                        selected.set(Some(i));
+                        scroll_into_view(&card_id(i));
+                    },
+                    span { class: "bbox-label", "{yolo.label_en} / {yolo.label_no}" }
+                }
+            }
+        }
+    }
+}
+
+fn card_id(index: usize) -> String {
+    format!("detection-{index}")
+}
+
+fn scroll_into_view(element_id: &str) {
+    if let Some(el) = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(element_id))
+    {
+        el.scroll_into_view();
+    }
+}
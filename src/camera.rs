@@ -1,113 +1,408 @@
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{
-    window, HtmlCanvasElement, HtmlVideoElement, MediaStreamConstraints, MediaStreamTrack,
+    window, Blob, CanvasRenderingContext2d, HtmlCanvasElement, HtmlVideoElement, ImageBitmap,
+    ImageBitmapOptions, ImageOrientation, MediaDeviceInfo, MediaDeviceKind, MediaStreamConstraints,
+    MediaStreamTrack,
 };
 
-/// Starts the camera and attaches the stream to a video element.
-pub async fn start_camera(video_id: &str) -> Result<(), String> {
-    let window = window().ok_or("no window")?;
-    let document = window.document().ok_or("no document")?;
-    let navigator = window.navigator();
+use netthinne_core::image_utils;
+
+use crate::error::{js_error, NetthinneError};
+
+/// Preferred stream resolution; the browser picks the closest the camera offers.
+const IDEAL_WIDTH: u32 = 1920;
+const IDEAL_HEIGHT: u32 = 1080;
+/// Longest side of a still after downscaling, to bound processing and encode time.
+const MAX_STILL_SIDE: u32 = 2048;
+
+/// A video input reported by `enumerateDevices`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraDevice {
+    pub device_id: String,
+    /// Empty until the user has granted camera permission.
+    pub label: String,
+}
+
+/// Which camera `start_camera` should open.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum CameraChoice {
+    /// The rear camera on phones (`facingMode: "environment"`).
+    #[default]
+    Back,
+    /// The selfie camera (`facingMode: "user"`).
+    Front,
+    /// A specific device from [`list_cameras`].
+    Device(String),
+}
+
+impl std::fmt::Display for CameraChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CameraChoice::Back => f.write_str("back"),
+            CameraChoice::Front => f.write_str("front"),
+            CameraChoice::Device(id) => write!(f, "device:{id}"),
+        }
+    }
+}
+
+impl std::str::FromStr for CameraChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "back" => Ok(CameraChoice::Back),
+            "front" => Ok(CameraChoice::Front),
+            _ => s
+                .strip_prefix("device:")
+                .map(|id| CameraChoice::Device(id.to_string()))
+                .ok_or_else(|| format!("unknown camera choice: {s}")),
+        }
+    }
+}
+
+/// Zoom range reported by the active track.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ZoomRange {
+    pub min: f64,
+    pub max: f64,
+    pub step: f64,
+}
+
+/// Optional controls the active camera track supports.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CameraCapabilities {
+    pub zoom: Option<ZoomRange>,
+    pub torch: bool,
+    /// Focus mode to pair with `pointsOfInterest` for tap-to-focus, if any.
+    pub focus_mode: Option<String>,
+}
+
+/// Lists the available video inputs.
+pub async fn list_cameras() -> Result<Vec<CameraDevice>, NetthinneError> {
+    let media_devices = window()
+        .ok_or(no("window"))?
+        .navigator()
+        .media_devices()
+        .map_err(|_| NetthinneError::NoCamera)?;
+
+    let promise = media_devices.enumerate_devices().map_err(|e| {
+        NetthinneError::Camera(format!("enumerateDevices failed: {}", js_error(&e)))
+    })?;
+    let devices: js_sys::Array = JsFuture::from(promise)
+        .await
+        .map_err(|e| NetthinneError::Camera(format!("enumerateDevices failed: {}", js_error(&e))))?
+        .unchecked_into();
+
+    Ok(devices
+        .iter()
+        .filter_map(|d| d.dyn_into::<MediaDeviceInfo>().ok())
+        .filter(|d| d.kind() == MediaDeviceKind::Videoinput)
+        .map(|d| CameraDevice {
+            device_id: d.device_id(),
+            label: d.label(),
+        })
+        .collect())
+}
+
+/// Starts the chosen camera and attaches the stream to a video element.
+pub async fn start_camera(video_id: &str, choice: &CameraChoice) -> Result<(), NetthinneError> {
+    let document = document()?;
+    let navigator = window().ok_or(no("window"))?.navigator();
+    // mediaDevices is missing outside secure contexts and on camera-less browsers.
     let media_devices = navigator
         .media_devices()
-        .map_err(|e| format!("no media devices: {:?}", e))?;
+        .map_err(|_| NetthinneError::NoCamera)?;
 
     let constraints = MediaStreamConstraints::new();
     constraints.set_audio(&JsValue::FALSE);
 
     let video_constraints = js_sys::Object::new();
-    js_sys::Reflect::set(
-        &video_constraints,
-        &"facingMode".into(),
-        &"environment".into(),
-    )
-    .map_err(|e| format!("failed to set facingMode: {:?}", e))?;
+    let (key, value): (&str, JsValue) = match choice {
+        CameraChoice::Back => ("facingMode", "environment".into()),
+        CameraChoice::Front => ("facingMode", "user".into()),
+        CameraChoice::Device(id) => {
+            let exact = js_sys::Object::new();
+            set(&exact, "exact", &id.into())?;
+            ("deviceId", exact.into())
+        }
+    };
+    set(&video_constraints, key, &value)?;
+    set(&video_constraints, "width", &ideal(IDEAL_WIDTH)?)?;
+    set(&video_constraints, "height", &ideal(IDEAL_HEIGHT)?)?;
     constraints.set_video(&video_constraints);
 
     let promise = media_devices
         .get_user_media_with_constraints(&constraints)
-        .map_err(|e| format!("getUserMedia failed: {:?}", e))?;
+        .map_err(|e| NetthinneError::from_media_error(&e))?;
 
     let stream = JsFuture::from(promise)
         .await
-        .map_err(|e| format!("camera stream error: {:?}", e))?;
-
-    let video_el: HtmlVideoElement = document
-        .get_element_by_id(video_id)
-        .ok_or("video element not found")?
-        .dyn_into()
-        .map_err(|_| "element is not a video")?;
+        .map_err(|e| NetthinneError::from_media_error(&e))?;
 
+    let video_el = video_element(&document, video_id)?;
     video_el.set_src_object(Some(
         &stream
             .dyn_into()
-            .map_err(|_| "stream is not a MediaStream")?,
+            .map_err(|_| no("MediaStream from getUserMedia"))?,
     ));
 
     Ok(())
 }
 
-/// Captures the current frame from the video element as RGBA pixel data.
+/// Captures the current frame from the video element as RGBA pixel data,
+/// downscaled like every still to at most `MAX_STILL_SIDE`.
 /// Returns (rgba_bytes, width, height).
-pub fn capture_frame(video_id: &str) -> Result<(Vec<u8>, u32, u32), String> {
-    let window = window().ok_or("no window")?;
-    let document = window.document().ok_or("no document")?;
+pub fn capture_frame(video_id: &str) -> Result<(Vec<u8>, u32, u32), NetthinneError> {
+    capture_preview(video_id, MAX_STILL_SIDE)
+}
 
-    let video_el: HtmlVideoElement = document
-        .get_element_by_id(video_id)
-        .ok_or("video element not found")?
-        .dyn_into()
-        .map_err(|_| "element is not a video")?;
+/// Like [`capture_frame`], but downscaled so the longest side is at most
+/// `max_side`. Used for live detection, where small frames keep up with the video.
+pub fn capture_preview(
+    video_id: &str,
+    max_side: u32,
+) -> Result<(Vec<u8>, u32, u32), NetthinneError> {
+    let document = document()?;
+    let video_el = video_element(&document, video_id)?;
+
+    if video_el.video_width() == 0 || video_el.video_height() == 0 {
+        return Err(NetthinneError::VideoNotReady);
+    }
+    let (width, height) =
+        image_utils::fitted_size(video_el.video_width(), video_el.video_height(), max_side);
 
-    let width = video_el.video_width();
-    let height = video_el.video_height();
+    let ctx = canvas_context(&document, width, height)?;
+    ctx.draw_image_with_html_video_element_and_dw_and_dh(
+        &video_el,
+        0.0,
+        0.0,
+        width as f64,
+        height as f64,
+    )
+    .map_err(|e| failed("drawImage", &e))?;
+
+    Ok((read_pixels(&ctx, width, height)?, width, height))
+}
 
-    if width == 0 || height == 0 {
-        return Err("video not ready".to_string());
+/// Captures a still photo, using `ImageCapture.takePhoto` for the sensor's full
+/// resolution when the browser supports it and the preview frame otherwise.
+/// Stills are downscaled so their longest side is at most `MAX_STILL_SIDE`.
+pub async fn take_photo(video_id: &str) -> Result<(Vec<u8>, u32, u32), NetthinneError> {
+    match take_photo_with_image_capture(video_id).await {
+        Ok(Some(frame)) => Ok(frame),
+        Ok(None) => capture_frame(video_id),
+        Err(e) => {
+            log::warn!("takePhoto failed, using preview frame: {e}");
+            capture_frame(video_id)
+        }
     }
+}
 
-    let canvas: HtmlCanvasElement = document
-        .create_element("canvas")
-        .map_err(|e| format!("failed to create canvas: {:?}", e))?
+/// Returns `Ok(None)` when ImageCapture is unavailable.
+async fn take_photo_with_image_capture(
+    video_id: &str,
+) -> Result<Option<(Vec<u8>, u32, u32)>, NetthinneError> {
+    let window = window().ok_or(no("window"))?;
+    // ImageCapture is not in stable web-sys, so it is reached through Reflect.
+    let Some(image_capture) = js_sys::Reflect::get(&window, &"ImageCapture".into())
+        .ok()
+        .and_then(|c| c.dyn_into::<js_sys::Function>().ok())
+    else {
+        return Ok(None);
+    };
+
+    let document = document()?;
+    let Some(track) = video_track(&video_element(&document, video_id)?) else {
+        return Ok(None);
+    };
+
+    let capture = js_sys::Reflect::construct(&image_capture, &js_sys::Array::of1(&track))
+        .map_err(|e| failed("new ImageCapture", &e))?;
+    let take_photo: js_sys::Function = js_sys::Reflect::get(&capture, &"takePhoto".into())
+        .map_err(|e| failed("takePhoto lookup", &e))?
         .dyn_into()
-        .map_err(|_| "element is not a canvas")?;
+        .map_err(|_| no("takePhoto method"))?;
+    let promise: js_sys::Promise = take_photo
+        .call0(&capture)
+        .map_err(|e| failed("takePhoto", &e))?
+        .dyn_into()
+        .map_err(|_| no("takePhoto promise"))?;
+    let blob: Blob = JsFuture::from(promise)
+        .await
+        .map_err(|e| failed("takePhoto", &e))?
+        .dyn_into()
+        .map_err(|_| no("photo blob"))?;
 
-    canvas.set_width(width);
-    canvas.set_height(height);
+    Ok(Some(decode_blob(&blob).await?))
+}
 
-    let ctx = canvas
-        .get_context("2d")
-        .map_err(|e| format!("failed to get 2d context: {:?}", e))?
-        .ok_or("no 2d context")?
-        .dyn_into::<web_sys::CanvasRenderingContext2d>()
-        .map_err(|_| "context is not CanvasRenderingContext2d")?;
+/// Decodes an image file into upright RGBA, downscaled like a still.
+/// JPEG, PNG and WebP are decoded in Rust so EXIF orientation is handled the same
+/// in every browser; anything else (HEIC on Safari) goes to the browser's codecs.
+/// Returns (rgba_bytes, width, height).
+pub async fn decode_image_file(bytes: &[u8]) -> Result<(Vec<u8>, u32, u32), NetthinneError> {
+    match image_utils::decode_image(bytes).and_then(|f| image_utils::fit_within(f, MAX_STILL_SIDE))
+    {
+        Ok(frame) => return Ok((frame.pixels, frame.width, frame.height)),
+        Err(e) => log::warn!("Decoding in Rust failed, trying the browser: {e}"),
+    }
 
-    ctx.draw_image_with_html_video_element(&video_el, 0.0, 0.0)
-        .map_err(|e| format!("drawImage failed: {:?}", e))?;
+    let blob =
+        Blob::new_with_u8_array_sequence(&js_sys::Array::of1(&js_sys::Uint8Array::from(bytes)))
+            .map_err(|e| failed("new Blob", &e))?;
+    decode_blob(&blob).await.map_err(|e| match e {
+        NetthinneError::Camera(detail) => NetthinneError::Preprocessing(detail),
+        other => other,
+    })
+}
 
-    let image_data = ctx
-        .get_image_data(0.0, 0.0, width as f64, height as f64)
-        .map_err(|e| format!("getImageData failed: {:?}", e))?;
+async fn decode_blob(blob: &Blob) -> Result<(Vec<u8>, u32, u32), NetthinneError> {
+    let window = window().ok_or(no("window"))?;
+    let options = ImageBitmapOptions::new();
+    options.set_image_orientation(ImageOrientation::FromImage);
+    let bitmap: ImageBitmap = JsFuture::from(
+        window
+            .create_image_bitmap_with_blob_and_image_bitmap_options(blob, &options)
+            .map_err(|e| failed("createImageBitmap", &e))?,
+    )
+    .await
+    .map_err(|e| failed("createImageBitmap", &e))?
+    .dyn_into()
+    .map_err(|_| no("ImageBitmap"))?;
+
+    let (width, height) = image_utils::fitted_size(bitmap.width(), bitmap.height(), MAX_STILL_SIDE);
+    let ctx = canvas_context(&document()?, width, height)?;
+    ctx.draw_image_with_image_bitmap_and_dw_and_dh(&bitmap, 0.0, 0.0, width as f64, height as f64)
+        .map_err(|e| failed("drawImage", &e))?;
+    bitmap.close();
 
-    Ok((image_data.data().to_vec(), width, height))
+    Ok((read_pixels(&ctx, width, height)?, width, height))
 }
 
-/// Stops all camera tracks and clears the video source.
-pub fn stop_camera(video_id: &str) -> Result<(), String> {
-    let window = window().ok_or("no window")?;
-    let document = window.document().ok_or("no document")?;
+/// Reads zoom, torch and focus support from `MediaStreamTrack.getCapabilities()`.
+/// Browsers without the method report no capabilities.
+pub fn capabilities(video_id: &str) -> Result<CameraCapabilities, NetthinneError> {
+    let track = active_track(video_id)?;
+    // getCapabilities is not in stable web-sys, so it is reached through Reflect.
+    let Some(get_capabilities) = js_sys::Reflect::get(&track, &"getCapabilities".into())
+        .ok()
+        .and_then(|f| f.dyn_into::<js_sys::Function>().ok())
+    else {
+        return Ok(CameraCapabilities::default());
+    };
+    let caps = get_capabilities
+        .call0(&track)
+        .map_err(|e| failed("getCapabilities", &e))?;
 
-    let video_el: HtmlVideoElement = document
-        .get_element_by_id(video_id)
-        .ok_or("video element not found")?
-        .dyn_into()
-        .map_err(|_| "element is not a video")?;
+    let get = |key: &str| js_sys::Reflect::get(&caps, &key.into()).unwrap_or(JsValue::UNDEFINED);
+    let number = |obj: &JsValue, key: &str| {
+        js_sys::Reflect::get(obj, &key.into())
+            .ok()
+            .and_then(|v| v.as_f64())
+    };
+
+    let zoom_caps = get("zoom");
+    let zoom = match (number(&zoom_caps, "min"), number(&zoom_caps, "max")) {
+        (Some(min), Some(max)) if max > min => Some(ZoomRange {
+            min,
+            max,
+            step: number(&zoom_caps, "step").unwrap_or(0.1),
+        }),
+        _ => None,
+    };
+
+    let torch = js_sys::Array::is_array(&get("torch"))
+        && js_sys::Array::from(&get("torch")).includes(&JsValue::TRUE, 0);
+
+    let focus_modes: Vec<String> = if js_sys::Array::is_array(&get("focusMode")) {
+        js_sys::Array::from(&get("focusMode"))
+            .iter()
+            .filter_map(|m| m.as_string())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let focus_mode = ["single-shot", "continuous"]
+        .into_iter()
+        .find(|mode| focus_modes.iter().any(|m| m == mode))
+        .map(str::to_string);
+
+    Ok(CameraCapabilities {
+        zoom,
+        torch,
+        focus_mode,
+    })
+}
+
+/// Sets the optical/digital zoom factor of the active track.
+pub async fn set_zoom(video_id: &str, zoom: f64) -> Result<(), NetthinneError> {
+    let constraint = js_sys::Object::new();
+    set(&constraint, "zoom", &zoom.into())?;
+    apply_advanced(video_id, &constraint).await
+}
+
+/// Turns the flashlight of the active track on or off.
+pub async fn set_torch(video_id: &str, on: bool) -> Result<(), NetthinneError> {
+    let constraint = js_sys::Object::new();
+    set(&constraint, "torch", &on.into())?;
+    apply_advanced(video_id, &constraint).await
+}
+
+/// Focuses on a point of the preview given in 0..1 coordinates of the frame.
+pub async fn focus_at(
+    video_id: &str,
+    x: f64,
+    y: f64,
+    focus_mode: &str,
+) -> Result<(), NetthinneError> {
+    let point = js_sys::Object::new();
+    set(&point, "x", &x.clamp(0.0, 1.0).into())?;
+    set(&point, "y", &y.clamp(0.0, 1.0).into())?;
+    let constraint = js_sys::Object::new();
+    set(&constraint, "pointsOfInterest", &js_sys::Array::of1(&point))?;
+    set(&constraint, "focusMode", &focus_mode.into())?;
+    apply_advanced(video_id, &constraint).await
+}
+
+/// Applies `{ advanced: [constraint] }` to the active track.
+async fn apply_advanced(video_id: &str, constraint: &js_sys::Object) -> Result<(), NetthinneError> {
+    let track = active_track(video_id)?;
+    let constraints = js_sys::Object::new();
+    set(&constraints, "advanced", &js_sys::Array::of1(constraint))?;
+    let promise = track
+        .apply_constraints_with_constraints(constraints.unchecked_ref())
+        .map_err(|e| failed("applyConstraints", &e))?;
+    JsFuture::from(promise)
+        .await
+        .map_err(|e| failed("applyConstraints", &e))?;
+    Ok(())
+}
+
+/// Converts a point in preview element pixels to 0..1 frame coordinates.
+pub fn preview_point(video_id: &str, x: f64, y: f64) -> Option<(f64, f64)> {
+    let video_el = video_element(&document().ok()?, video_id).ok()?;
+    let (w, h) = (video_el.client_width(), video_el.client_height());
+    if w == 0 || h == 0 {
+        return None;
+    }
+    Some((x / w as f64, y / h as f64))
+}
+
+fn active_track(video_id: &str) -> Result<MediaStreamTrack, NetthinneError> {
+    let video_el = video_element(&document()?, video_id)?;
+    video_track(&video_el).ok_or(NetthinneError::VideoNotReady)
+}
+
+/// Stops all camera tracks and clears the video source.
+pub fn stop_camera(video_id: &str) -> Result<(), NetthinneError> {
+    let document = document()?;
+    let video_el = video_element(&document, video_id)?;
 
     if let Some(stream) = video_el.src_object() {
         let media_stream: web_sys::MediaStream = stream
             .dyn_into()
-            .map_err(|_| "srcObject is not a MediaStream")?;
+            .map_err(|_| no("MediaStream in srcObject"))?;
 
         let tracks = media_stream.get_tracks();
         for i in 0..tracks.length() {
@@ -120,3 +415,81 @@ pub fn stop_camera(video_id: &str) -> Result<(), String> {
     video_el.set_src_object(None);
     Ok(())
 }
+
+/// First video track of the stream attached to `video_el`, if any.
+fn video_track(video_el: &HtmlVideoElement) -> Option<MediaStreamTrack> {
+    let stream: web_sys::MediaStream = video_el.src_object()?.dyn_into().ok()?;
+    stream.get_video_tracks().get(0).dyn_into().ok()
+}
+
+fn canvas_context(
+    document: &web_sys::Document,
+    width: u32,
+    height: u32,
+) -> Result<CanvasRenderingContext2d, NetthinneError> {
+    let canvas: HtmlCanvasElement = document
+        .create_element("canvas")
+        .map_err(|e| failed("create canvas", &e))?
+        .dyn_into()
+        .map_err(|_| no("canvas element"))?;
+
+    canvas.set_width(width);
+    canvas.set_height(height);
+
+    canvas
+        .get_context("2d")
+        .map_err(|e| failed("get 2d context", &e))?
+        .ok_or(no("2d context"))?
+        .dyn_into::<CanvasRenderingContext2d>()
+        .map_err(|_| no("CanvasRenderingContext2d"))
+}
+
+fn read_pixels(
+    ctx: &CanvasRenderingContext2d,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, NetthinneError> {
+    let image_data = ctx
+        .get_image_data(0.0, 0.0, width as f64, height as f64)
+        .map_err(|e| failed("getImageData", &e))?;
+    Ok(image_data.data().to_vec())
+}
+
+/// `{ ideal: value }` constraint object.
+fn ideal(value: u32) -> Result<JsValue, NetthinneError> {
+    let constraint = js_sys::Object::new();
+    set(&constraint, "ideal", &value.into())?;
+    Ok(constraint.into())
+}
+
+fn set(target: &js_sys::Object, key: &str, value: &JsValue) -> Result<(), NetthinneError> {
+    js_sys::Reflect::set(target, &key.into(), value)
+        .map(|_| ())
+        .map_err(|e| NetthinneError::Camera(format!("failed to set {key}: {}", js_error(&e))))
+}
+
+fn document() -> Result<web_sys::Document, NetthinneError> {
+    window()
+        .ok_or(no("window"))?
+        .document()
+        .ok_or(no("document"))
+}
+
+fn video_element(
+    document: &web_sys::Document,
+    video_id: &str,
+) -> Result<HtmlVideoElement, NetthinneError> {
+    document
+        .get_element_by_id(video_id)
+        .ok_or(no("video element"))?
+        .dyn_into()
+        .map_err(|_| no("video element"))
+}
+
+fn failed(what: &str, e: &JsValue) -> NetthinneError {
+    NetthinneError::Camera(format!("{what} failed: {}", js_error(e)))
+}
+
+fn no(what: &str) -> NetthinneError {
+    NetthinneError::Camera(format!("no {what}"))
+}
@@ -0,0 +1,104 @@
+//! Shared IndexedDB handle for the stores that outgrow localStorage.
+
+use std::cell::RefCell;
+
+use js_sys::Promise;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    window, IdbDatabase, IdbObjectStore, IdbObjectStoreParameters, IdbOpenDbRequest, IdbRequest,
+    IdbTransactionMode,
+};
+
+use crate::error::{js_error, NetthinneError};
+
+const DB_NAME: &str = "netthinne";
+/// Bump together with `STORES` when adding a store; upgrades create what is missing.
+const DB_VERSION: u32 = 2;
+/// Scans from the history screen, keyed by an auto-incremented number.
+pub const SCANS_STORE: &str = "scans";
+/// Flashcards, keyed by their card id.
+pub const CARDS_STORE: &str = "cards";
+/// (name, auto-increment keys)
+const STORES: [(&str, bool); 2] = [(SCANS_STORE, true), (CARDS_STORE, false)];
+
+thread_local! {
+    static DB: RefCell<Option<IdbDatabase>> = const { RefCell::new(None) };
+}
+
+/// Opens `name` in a new transaction.
+pub async fn store(name: &str, mode: IdbTransactionMode) -> Result<IdbObjectStore, NetthinneError> {
+    database()
+        .await?
+        .transaction_with_str_and_mode(name, mode)
+        .map_err(|e| failed("start transaction", &e))?
+        .object_store(name)
+        .map_err(|e| failed("open store", &e))
+}
+
+/// Opens the database once and keeps the handle for later calls.
+async fn database() -> Result<IdbDatabase, NetthinneError> {
+    if let Some(db) = DB.with(|cell| cell.borrow().clone()) {
+        return Ok(db);
+    }
+
+    let factory = window()
+        .ok_or_else(|| NetthinneError::Storage("no window".into()))?
+        .indexed_db()
+        .map_err(|e| failed("IndexedDB", &e))?
+        .ok_or_else(|| NetthinneError::Storage("IndexedDB unavailable".into()))?;
+    let request: IdbOpenDbRequest = factory
+        .open_with_u32(DB_NAME, DB_VERSION)
+        .map_err(|e| failed("open database", &e))?;
+
+    let upgrade_request = request.clone();
+    let on_upgrade = Closure::<dyn FnMut()>::new(move || {
+        let Ok(db) = upgrade_request
+            .result()
+            .map(JsValue::unchecked_into::<IdbDatabase>)
+        else {
+            return;
+        };
+        for (name, auto_increment) in STORES {
+            if db.object_store_names().contains(name) {
+                continue;
+            }
+            let params = IdbObjectStoreParameters::new();
+            params.set_auto_increment(auto_increment);
+            if let Err(e) = db.create_object_store_with_optional_parameters(name, &params) {
+                log::error!("Creating the {name} store failed: {}", js_error(&e));
+            }
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+
+    // `on_upgrade` must outlive the request, which settles after the upgrade.
+    let db: IdbDatabase = wait(&request).await?.unchecked_into();
+    request.set_onupgradeneeded(None);
+    drop(on_upgrade);
+
+    DB.with(|cell| *cell.borrow_mut() = Some(db.clone()));
+    Ok(db)
+}
+
+/// Resolves with the request's result once it succeeds.
+pub async fn wait(request: &IdbRequest) -> Result<JsValue, NetthinneError> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    if JsFuture::from(promise).await.is_err() {
+        let detail = request
+            .error()
+            .ok()
+            .flatten()
+            .map(|e| format!("{}: {}", e.name(), e.message()))
+            .unwrap_or_else(|| "unknown error".into());
+        return Err(NetthinneError::Storage(detail));
+    }
+    request.result().map_err(|e| failed("read result", &e))
+}
+
+pub fn failed(what: &str, e: &JsValue) -> NetthinneError {
+    NetthinneError::Storage(format!("{what} failed: {}", js_error(e)))
+}
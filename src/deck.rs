@@ -0,0 +1,311 @@
+//! Vocabulary flashcards built from scanned objects, scheduled with SM-2.
+//!
+//! Every word becomes two cards, one per quiz direction, so each direction
+//! keeps its own schedule. Cards are stored as JSON in IndexedDB keyed by id.
+
+use js_sys::Array;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{IdbObjectStore, IdbTransactionMode};
+
+use netthinne_core::ml::DetectedObject;
+
+use crate::db::{self, failed, wait, CARDS_STORE};
+use crate::error::NetthinneError;
+
+const DAY_MS: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+/// A lapsed card comes back this soon so it is seen again in the same session.
+const RELEARN_MS: f64 = 10.0 * 60.0 * 1000.0;
+const INITIAL_EASE: f32 = 2.5;
+const MIN_EASE: f32 = 1.3;
+
+/// Which side of the word is shown as the prompt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Direction {
+    EnglishToNorwegian,
+    NorwegianToEnglish,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 2] = [Direction::EnglishToNorwegian, Direction::NorwegianToEnglish];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::EnglishToNorwegian => "en-no",
+            Direction::NorwegianToEnglish => "no-en",
+        }
+    }
+}
+
+/// How well the answer was recalled, mapped onto SM-2 quality 0..=5.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Grade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Grade {
+    pub const ALL: [Grade; 4] = [Grade::Again, Grade::Hard, Grade::Good, Grade::Easy];
+
+    fn quality(self) -> u8 {
+        match self {
+            Grade::Again => 1,
+            Grade::Hard => 3,
+            Grade::Good => 4,
+            Grade::Easy => 5,
+        }
+    }
+}
+
+/// SM-2 state of one card.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    /// Successful reviews in a row.
+    pub repetitions: u32,
+    pub interval_days: u32,
+    pub ease: f32,
+    /// When the card is next due, in milliseconds since the Unix epoch.
+    pub due: f64,
+}
+
+impl Schedule {
+    /// A new card, due immediately.
+    pub fn new(now: f64) -> Self {
+        Self {
+            repetitions: 0,
+            interval_days: 0,
+            ease: INITIAL_EASE,
+            due: now,
+        }
+    }
+
+    pub fn is_due(&self, now: f64) -> bool {
+        self.due <= now
+    }
+
+    /// Applies one review at time `now`.
+    pub fn review(&mut self, grade: Grade, now: f64) {
+        let q = grade.quality();
+        if q < 3 {
+            self.repetitions = 0;
+            self.interval_days = 0;
+            self.due = now + RELEARN_MS;
+        } else {
+            self.interval_days = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval_days as f32 * self.ease).round() as u32,
+            };
+            self.repetitions += 1;
+            self.due = now + self.interval_days as f64 * DAY_MS;
+        }
+        let miss = (5 - q) as f32;
+        self.ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE);
+    }
+}
+
+/// One flashcard: a word quizzed in one direction.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Card {
+    pub label_en: String,
+    pub label_no: String,
+    /// Crop of the scanned object, shown on the front.
+    pub image_data_url: String,
+    pub direction: Direction,
+    pub schedule: Schedule,
+}
+
+impl Card {
+    /// Storage key; adding the same word again keeps the existing schedule.
+    pub fn id(&self) -> String {
+        format!("{}:{}", self.direction.as_str(), self.label_en)
+    }
+
+    pub fn prompt(&self) -> &str {
+        match self.direction {
+            Direction::EnglishToNorwegian => &self.label_en,
+            Direction::NorwegianToEnglish => &self.label_no,
+        }
+    }
+
+    pub fn answer(&self) -> &str {
+        match self.direction {
+            Direction::EnglishToNorwegian => &self.label_no,
+            Direction::NorwegianToEnglish => &self.label_en,
+        }
+    }
+}
+
+/// The (English, Norwegian) word a detection teaches: the YOLO label, or the
+/// top ImageNet class for whole-image results.
+pub fn word(detection: &DetectedObject) -> Option<(&str, &str)> {
+    match &detection.yolo {
+        Some(yolo) => Some((&yolo.label_en, &yolo.label_no)),
+        None => detection
+            .inet_predictions
+            .first()
+            .map(|p| (p.label_en.as_str(), p.label_no.as_str())),
+    }
+}
+
+/// Adds both cards for the detection's word unless they already exist.
+pub async fn add_word(detection: &DetectedObject, now: f64) -> Result<(), NetthinneError> {
+    let Some((label_en, label_no)) = word(detection) else {
+        return Ok(());
+    };
+    let store = cards_store(IdbTransactionMode::Readwrite).await?;
+    for direction in Direction::ALL {
+        let card = Card {
+            label_en: label_en.to_string(),
+            label_no: label_no.to_string(),
+            image_data_url: detection.image_data_url.clone(),
+            direction,
+            schedule: Schedule::new(now),
+        };
+        let key = JsValue::from(card.id());
+        let existing = wait(
+            &store
+                .count_with_key(&key)
+                .map_err(|e| failed("count card", &e))?,
+        )
+        .await?
+        .as_f64()
+        .unwrap_or(0.0);
+        if existing > 0.0 {
+            continue;
+        }
+        wait(
+            &store
+                .put_with_key(&encode(&card)?.into(), &key)
+                .map_err(|e| failed("add card", &e))?,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Stores a reviewed card.
+pub async fn save_card(card: &Card) -> Result<(), NetthinneError> {
+    let store = cards_store(IdbTransactionMode::Readwrite).await?;
+    let request = store
+        .put_with_key(&encode(card)?.into(), &card.id().into())
+        .map_err(|e| failed("save card", &e))?;
+    wait(&request).await?;
+    Ok(())
+}
+
+/// Every card in the deck. Entries that no longer parse are skipped.
+pub async fn load_cards() -> Result<Vec<Card>, NetthinneError> {
+    let store = cards_store(IdbTransactionMode::Readonly).await?;
+    let values: Array = wait(&store.get_all().map_err(|e| failed("list cards", &e))?)
+        .await?
+        .unchecked_into();
+    Ok(values
+        .iter()
+        .filter_map(|value| match serde_json::from_str(&value.as_string()?) {
+            Ok(card) => Some(card),
+            Err(e) => {
+                log::warn!("Skipping unreadable card: {e}");
+                None
+            }
+        })
+        .collect())
+}
+
+/// Due cards, most overdue first.
+pub fn due_cards(cards: &[Card], now: f64) -> Vec<Card> {
+    let mut due: Vec<Card> = cards
+        .iter()
+        .filter(|c| c.schedule.is_due(now))
+        .cloned()
+        .collect();
+    due.sort_by(|a, b| a.schedule.due.total_cmp(&b.schedule.due));
+    due
+}
+
+/// Number of due cards in `direction`.
+pub fn due_count(cards: &[Card], direction: Direction, now: f64) -> usize {
+    cards
+        .iter()
+        .filter(|c| c.direction == direction && c.schedule.is_due(now))
+        .count()
+}
+
+fn encode(card: &Card) -> Result<String, NetthinneError> {
+    serde_json::to_string(card)
+        .map_err(|e| NetthinneError::Storage(format!("failed to encode card: {e}")))
+}
+
+async fn cards_store(mode: IdbTransactionMode) -> Result<IdbObjectStore, NetthinneError> {
+    db::store(CARDS_STORE, mode).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn good_reviews_follow_sm2_intervals() {
+        let mut s = Schedule::new(0.0);
+        s.review(Grade::Good, 0.0);
+        assert_eq!((s.repetitions, s.interval_days), (1, 1));
+        s.review(Grade::Good, s.due);
+        assert_eq!((s.repetitions, s.interval_days), (2, 6));
+        s.review(Grade::Good, s.due);
+        // Good leaves the ease at 2.5, so 6 days becomes 15.
+        assert_eq!((s.repetitions, s.interval_days), (3, 15));
+        assert!((s.ease - INITIAL_EASE).abs() < 1e-6);
+        assert_eq!(s.due, 22.0 * DAY_MS);
+    }
+
+    #[test]
+    fn again_resets_and_relearns_soon() {
+        let mut s = Schedule::new(0.0);
+        s.review(Grade::Good, 0.0);
+        s.review(Grade::Good, s.due);
+        let now = s.due;
+        s.review(Grade::Again, now);
+        assert_eq!((s.repetitions, s.interval_days), (0, 0));
+        assert_eq!(s.due, now + RELEARN_MS);
+        assert!(s.ease < INITIAL_EASE);
+    }
+
+    #[test]
+    fn ease_moves_with_grade_and_has_a_floor() {
+        let mut easy = Schedule::new(0.0);
+        easy.review(Grade::Easy, 0.0);
+        assert!(easy.ease > INITIAL_EASE);
+
+        let mut hard = Schedule::new(0.0);
+        for _ in 0..20 {
+            hard.review(Grade::Hard, hard.due);
+        }
+        assert_eq!(hard.ease, MIN_EASE);
+        assert!(hard.interval_days > 6);
+    }
+
+    #[test]
+    fn due_cards_are_filtered_and_sorted() {
+        let card = |label: &str, due: f64| Card {
+            label_en: label.into(),
+            label_no: label.into(),
+            image_data_url: String::new(),
+            direction: Direction::EnglishToNorwegian,
+            schedule: Schedule {
+                due,
+                ..Schedule::new(0.0)
+            },
+        };
+        let cards = [card("later", 50.0), card("b", 20.0), card("a", 10.0)];
+        let due: Vec<_> = due_cards(&cards, 30.0)
+            .into_iter()
+            .map(|c| c.label_en)
+            .collect();
+        assert_eq!(due, ["a", "b"]);
+        assert_eq!(due_count(&cards, Direction::EnglishToNorwegian, 30.0), 2);
+        assert_eq!(due_count(&cards, Direction::NorwegianToEnglish, 30.0), 0);
+    }
+}
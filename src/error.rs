@@ -0,0 +1,106 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+/// Everything that can go wrong between opening the camera and showing results.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum NetthinneError {
+    /// The user or browser refused camera access.
+    CameraPermissionDenied,
+    /// No camera exists, or none matches the requested constraints.
+    NoCamera,
+    /// The video stream has no frame to capture yet.
+    VideoNotReady,
+    /// Any other browser failure while driving the camera or the page.
+    Camera(String),
+    /// Converting, cropping or encoding pixels failed.
+    Preprocessing(String),
+    /// A model produced output that could not be read.
+    Inference(String),
+    /// The inference worker could not be started or stopped responding.
+    Worker(String),
+    /// Browser storage could not be read or written.
+    Storage(String),
+    /// The browser has no Norwegian text-to-speech voice.
+    NoNorwegianVoice,
+    /// Speech synthesis is unavailable or failed.
+    Speech(String),
+    /// Building or downloading an export file failed.
+    Export(String),
+}
+
+impl NetthinneError {
+    /// Short user-facing message in Norwegian; `Display` gives the English one.
+    pub fn norwegian(&self) -> &'static str {
+        match self {
+            NetthinneError::CameraPermissionDenied => "Tilgang til kameraet ble avslått",
+            NetthinneError::NoCamera => "Fant ikke noe kamera",
+            NetthinneError::VideoNotReady => "Kameraet er ikke klart ennå",
+            NetthinneError::Camera(_) => "Kameraet fungerer ikke",
+            NetthinneError::Preprocessing(_) => "Bildet kunne ikke behandles",
+            NetthinneError::Inference(_) | NetthinneError::Worker(_) => "Gjenkjenningen mislyktes",
+            NetthinneError::Storage(_) => "Lagring i nettleseren mislyktes",
+            NetthinneError::NoNorwegianVoice => "Ingen norsk stemme er installert",
+            NetthinneError::Speech(_) => "Opplesningen mislyktes",
+            NetthinneError::Export(_) => "Eksporten mislyktes",
+        }
+    }
+
+    /// Maps a rejected `getUserMedia` promise to a specific variant by DOMException name.
+    pub fn from_media_error(e: &JsValue) -> Self {
+        let name = js_sys::Reflect::get(e, &"name".into())
+            .ok()
+            .and_then(|n| n.as_string())
+            .unwrap_or_default();
+        match name.as_str() {
+            "NotAllowedError" | "SecurityError" => NetthinneError::CameraPermissionDenied,
+            "NotFoundError" | "OverconstrainedError" => NetthinneError::NoCamera,
+            _ => NetthinneError::Camera(js_error(e)),
+        }
+    }
+}
+
+impl fmt::Display for NetthinneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetthinneError::CameraPermissionDenied => write!(f, "Camera access was denied"),
+            NetthinneError::NoCamera => write!(f, "No camera found"),
+            NetthinneError::VideoNotReady => write!(f, "The camera is not ready yet"),
+            NetthinneError::Camera(detail) => write!(f, "Camera error: {detail}"),
+            NetthinneError::Preprocessing(detail) => {
+                write!(f, "Image processing failed: {detail}")
+            }
+            NetthinneError::Inference(detail) => write!(f, "Recognition failed: {detail}"),
+            NetthinneError::Worker(detail) => write!(f, "Inference worker failed: {detail}"),
+            NetthinneError::Storage(detail) => write!(f, "Storage error: {detail}"),
+            NetthinneError::NoNorwegianVoice => {
+                write!(f, "No Norwegian voice is installed for text-to-speech")
+            }
+            NetthinneError::Speech(detail) => write!(f, "Speech failed: {detail}"),
+            NetthinneError::Export(detail) => write!(f, "Export failed: {detail}"),
+        }
+    }
+}
+
+impl std::error::Error for NetthinneError {}
+
+impl From<netthinne_core::Error> for NetthinneError {
+    fn from(e: netthinne_core::Error) -> Self {
+        match e {
+            netthinne_core::Error::Preprocessing(detail) => NetthinneError::Preprocessing(detail),
+            netthinne_core::Error::Inference(detail) => NetthinneError::Inference(detail),
+        }
+    }
+}
+
+/// Best-effort readable text for a thrown JS value.
+pub fn js_error(e: &JsValue) -> String {
+    e.as_string()
+        .or_else(|| {
+            js_sys::Reflect::get(e, &"message".into())
+                .ok()
+                .and_then(|m| m.as_string())
+        })
+        .unwrap_or_else(|| format!("{:?}", e))
+}
@@ -0,0 +1,476 @@
+//! Anki `.apkg` packages: a zip holding a `collection.anki2` SQLite database in
+//! the legacy schema 11, a `media` index and the card pictures.
+//!
+//! Each word becomes one note with two cards, matching the two review
+//! directions. Note type and deck ids are fixed and note GUIDs derive from the
+//! English label, so importing a newer export updates the same notes.
+
+use std::collections::BTreeMap;
+use std::io::{Cursor, Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::json;
+use sha1::{Digest, Sha1};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use super::group_words;
+use super::sqlite::{write_database, Index, Table, Value};
+use crate::deck::{Card, Direction};
+use crate::error::NetthinneError;
+
+const MODEL_ID: i64 = 1_700_000_000_001;
+const DECK_ID: i64 = 1_700_000_000_002;
+const DECK_NAME: &str = "Netthinne";
+const FIELD_SEPARATOR: char = '\u{1f}';
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+const CSS: &str = ".card { font-family: sans-serif; font-size: 28px; text-align: center; }
+.card img { max-width: 90%; max-height: 40vh; }";
+
+const COL_SQL: &str = "CREATE TABLE col (id integer primary key, crt integer not null, \
+         mod integer not null, scm integer not null, ver integer not null, \
+         dty integer not null, usn integer not null, ls integer not null, \
+         conf text not null, models text not null, decks text not null, \
+         dconf text not null, tags text not null)";
+const NOTES_SQL: &str = "CREATE TABLE notes (id integer primary key, guid text not null, \
+         mid integer not null, mod integer not null, usn integer not null, \
+         tags text not null, flds text not null, sfld integer not null, \
+         csum integer not null, flags integer not null, data text not null)";
+const CARDS_SQL: &str = "CREATE TABLE cards (id integer primary key, nid integer not null, \
+         did integer not null, ord integer not null, mod integer not null, \
+         usn integer not null, type integer not null, queue integer not null, \
+         due integer not null, ivl integer not null, factor integer not null, \
+         reps integer not null, lapses integer not null, left integer not null, \
+         odue integer not null, odid integer not null, flags integer not null, \
+         data text not null)";
+const REVLOG_SQL: &str = "CREATE TABLE revlog (id integer primary key, cid integer not null, \
+         usn integer not null, ease integer not null, ivl integer not null, \
+         lastIvl integer not null, factor integer not null, time integer not null, \
+         type integer not null)";
+const GRAVES_SQL: &str = "CREATE TABLE graves (usn integer not null, oid integer not null, \
+         type integer not null)";
+
+const INDEXES: [Index; 7] = [
+    Index {
+        name: "ix_notes_usn",
+        table: "notes",
+        sql: "CREATE INDEX ix_notes_usn on notes (usn)",
+        columns: &[4],
+    },
+    Index {
+        name: "ix_cards_usn",
+        table: "cards",
+        sql: "CREATE INDEX ix_cards_usn on cards (usn)",
+        columns: &[5],
+    },
+    Index {
+        name: "ix_revlog_usn",
+        table: "revlog",
+        sql: "CREATE INDEX ix_revlog_usn on revlog (usn)",
+        columns: &[2],
+    },
+    Index {
+        name: "ix_cards_nid",
+        table: "cards",
+        sql: "CREATE INDEX ix_cards_nid on cards (nid)",
+        columns: &[1],
+    },
+    Index {
+        name: "ix_cards_sched",
+        table: "cards",
+        sql: "CREATE INDEX ix_cards_sched on cards (did, queue, due)",
+        columns: &[2, 7, 8],
+    },
+    Index {
+        name: "ix_revlog_cid",
+        table: "revlog",
+        sql: "CREATE INDEX ix_revlog_cid on revlog (cid)",
+        columns: &[1],
+    },
+    Index {
+        name: "ix_notes_csum",
+        table: "notes",
+        sql: "CREATE INDEX ix_notes_csum on notes (csum)",
+        columns: &[8],
+    },
+];
+
+/// Builds an `.apkg` for `cards`, carrying over their review schedules.
+/// `now` is in milliseconds since the Unix epoch.
+pub fn build_apkg(cards: &[Card], now: f64) -> Result<Vec<u8>, NetthinneError> {
+    let words = group_words(cards);
+    let now_ms = now as i64;
+    let now_secs = now_ms / 1000;
+    // Review due dates are day numbers counted from the collection's creation.
+    let earliest = cards
+        .iter()
+        .map(|c| c.schedule.due as i64 / 1000)
+        .fold(now_secs, i64::min);
+    let crt = earliest - earliest.rem_euclid(DAY_SECS);
+
+    let mut notes = Vec::new();
+    let mut anki_cards = Vec::new();
+    let mut media = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        let note_id = now_ms + i as i64;
+        let picture = match data_url_bytes(word.image_data_url) {
+            Some((extension, bytes)) => {
+                let name = format!("netthinne-{i}.{extension}");
+                let tag = format!("<img src=\"{name}\">");
+                media.push((name, bytes));
+                tag
+            }
+            None => String::new(),
+        };
+        let fields = [word.label_en, word.label_no, &picture].join(&FIELD_SEPARATOR.to_string());
+        notes.push((
+            note_id,
+            vec![
+                Value::Null,
+                format!("netthinne:{}", word.label_en).into(),
+                MODEL_ID.into(),
+                now_secs.into(),
+                Value::Integer(-1),
+                "".into(),
+                fields.into(),
+                word.label_en.into(),
+                checksum(word.label_en).into(),
+                Value::Integer(0),
+                "".into(),
+            ],
+        ));
+        for card in &word.cards {
+            let ord = template_ord(card.direction);
+            anki_cards.push((
+                now_ms + 2 * i as i64 + ord,
+                card_row(card, note_id, ord, i as i64, now_secs, crt),
+            ));
+        }
+    }
+
+    let col = vec![
+        Value::Null,
+        crt.into(),
+        now_ms.into(),
+        now_ms.into(),
+        Value::Integer(11),
+        Value::Integer(0),
+        Value::Integer(0),
+        Value::Integer(0),
+        collection_config().to_string().into(),
+        models(now_secs).to_string().into(),
+        decks(now_secs).to_string().into(),
+        deck_config().to_string().into(),
+        "{}".into(),
+    ];
+    let table = |name, sql, rows| Table { name, sql, rows };
+    let tables = [
+        table("col", COL_SQL, vec![(1, col)]),
+        table("notes", NOTES_SQL, notes),
+        table("cards", CARDS_SQL, anki_cards),
+        table("revlog", REVLOG_SQL, Vec::new()),
+        table("graves", GRAVES_SQL, Vec::new()),
+    ];
+    let database = write_database(&tables, &INDEXES)?;
+
+    zip_package(&database, &media)
+        .map_err(|e| NetthinneError::Export(format!("failed to write .apkg: {e}")))
+}
+
+fn template_ord(direction: Direction) -> i64 {
+    match direction {
+        Direction::EnglishToNorwegian => 0,
+        Direction::NorwegianToEnglish => 1,
+    }
+}
+
+/// A `cards` row. Cards never answered correctly stay new; the rest become
+/// review cards with the same interval, ease and due day.
+fn card_row(
+    card: &Card,
+    note_id: i64,
+    ord: i64,
+    position: i64,
+    now_secs: i64,
+    crt: i64,
+) -> Vec<Value> {
+    let s = &card.schedule;
+    let (kind, due, ivl, factor) = if s.repetitions == 0 {
+        (0, position, 0, 0)
+    } else {
+        let due_day = (s.due as i64 / 1000 - crt).div_euclid(DAY_SECS);
+        (
+            2,
+            due_day,
+            s.interval_days as i64,
+            (s.ease * 1000.0).round() as i64,
+        )
+    };
+    vec![
+        Value::Null,
+        note_id.into(),
+        DECK_ID.into(),
+        ord.into(),
+        now_secs.into(),
+        Value::Integer(-1),
+        // type and queue share the same codes for new and review cards.
+        kind.into(),
+        kind.into(),
+        due.into(),
+        ivl.into(),
+        factor.into(),
+        (s.repetitions as i64).into(),
+        Value::Integer(0),
+        Value::Integer(0),
+        Value::Integer(0),
+        Value::Integer(0),
+        Value::Integer(0),
+        "".into(),
+    ]
+}
+
+/// Anki's duplicate-check checksum: the first 8 hex digits of the SHA-1 of
+/// the sort field.
+fn checksum(field: &str) -> i64 {
+    let digest = Sha1::digest(field.as_bytes());
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) as i64
+}
+
+/// Decodes a base64 image data URL into a file extension and its bytes.
+fn data_url_bytes(url: &str) -> Option<(&'static str, Vec<u8>)> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let extension = match header.strip_suffix(";base64")? {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/webp" => "webp",
+        _ => return None,
+    };
+    Some((extension, STANDARD.decode(data).ok()?))
+}
+
+fn zip_package(database: &[u8], media: &[(String, Vec<u8>)]) -> zip::result::ZipResult<Vec<u8>> {
+    // Pictures are already compressed and the database is small, so store as is.
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+    zip.start_file("collection.anki2", options)?;
+    zip.write_all(database)?;
+
+    let index: BTreeMap<String, &str> = media
+        .iter()
+        .enumerate()
+        .map(|(i, (name, _))| (i.to_string(), name.as_str()))
+        .collect();
+    zip.start_file("media", options)?;
+    zip.write_all(json!(index).to_string().as_bytes())?;
+    for (i, (_, bytes)) in media.iter().enumerate() {
+        zip.start_file(i.to_string(), options)?;
+        zip.write_all(bytes)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+fn collection_config() -> serde_json::Value {
+    json!({
+        "activeDecks": [1],
+        "curDeck": 1,
+        "newSpread": 0,
+        "collapseTime": 1200,
+        "timeLim": 0,
+        "estTimes": true,
+        "dueCounts": true,
+        "curModel": null,
+        "nextPos": 1,
+        "sortType": "noteFld",
+        "sortBackwards": false,
+        "addToCur": true
+    })
+}
+
+fn models(now_secs: i64) -> serde_json::Value {
+    let field = |name: &str, ord: u32| {
+        json!({
+            "name": name, "ord": ord, "sticky": false, "rtl": false,
+            "font": "Arial", "size": 20, "media": []
+        })
+    };
+    let template = |name: &str, ord: u32, front: &str, back: &str| {
+        json!({
+            "name": name, "ord": ord, "qfmt": format!("{{{{Picture}}}}<div>{{{{{front}}}}}</div>"),
+            "afmt": format!("{{{{FrontSide}}}}<hr id=answer>{{{{{back}}}}}"),
+            "did": null, "bqfmt": "", "bafmt": ""
+        })
+    };
+    json!({
+        MODEL_ID.to_string(): {
+            "id": MODEL_ID,
+            "name": "Netthinne (English/Norwegian)",
+            "type": 0,
+            "mod": now_secs,
+            "usn": -1,
+            "sortf": 0,
+            "did": DECK_ID,
+            "flds": [field("English", 0), field("Norwegian", 1), field("Picture", 2)],
+            "tmpls": [
+                template("English → Norwegian", 0, "English", "Norwegian"),
+                template("Norwegian → English", 1, "Norwegian", "English"),
+            ],
+            "css": CSS,
+            "latexPre": "\\documentclass[12pt]{article}\n\\begin{document}\n",
+            "latexPost": "\\end{document}",
+            "latexsvg": false,
+            "req": [[0, "any", [0]], [1, "any", [1]]],
+            "tags": [],
+            "vers": []
+        }
+    })
+}
+
+fn decks(now_secs: i64) -> serde_json::Value {
+    let deck = |id: i64, name: &str| {
+        json!({
+            "id": id, "name": name, "mod": now_secs, "usn": -1, "desc": "",
+            "dyn": 0, "conf": 1, "collapsed": false, "browserCollapsed": false,
+            "extendNew": 0, "extendRev": 0,
+            "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0], "timeToday": [0, 0]
+        })
+    };
+    json!({
+        "1": deck(1, "Default"),
+        DECK_ID.to_string(): deck(DECK_ID, DECK_NAME),
+    })
+}
+
+fn deck_config() -> serde_json::Value {
+    json!({
+        "1": {
+            "id": 1, "name": "Default", "mod": 0, "usn": 0, "dyn": false,
+            "maxTaken": 60, "timer": 0, "autoplay": true, "replayq": true,
+            "new": {
+                "delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500,
+                "order": 1, "perDay": 20, "bury": false, "separate": true
+            },
+            "rev": {
+                "perDay": 200, "ease4": 1.3, "fuzz": 0.05, "minSpace": 1,
+                "ivlFct": 1, "maxIvl": 36500, "bury": false, "hardFactor": 1.2
+            },
+            "lapse": {
+                "delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::deck::{Grade, Schedule};
+    use crate::export::sqlite::tests::{open_checked, query_plan};
+
+    const PIXEL_PNG: &str = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+
+    #[test]
+    fn package_holds_database_and_one_picture_per_word() {
+        let now = 1.7e12;
+        let mut cards = Vec::new();
+        for (label_en, label_no) in [("cat", "katt"), ("bicycle", "sykkel")] {
+            for direction in Direction::ALL {
+                cards.push(Card {
+                    label_en: label_en.into(),
+                    label_no: label_no.into(),
+                    image_data_url: PIXEL_PNG.into(),
+                    direction,
+                    schedule: Schedule::new(now),
+                });
+            }
+        }
+        cards[0].schedule.review(Grade::Good, now);
+
+        let bytes = build_apkg(&cards, now).unwrap();
+        let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let names: Vec<&str> = zip.file_names().collect();
+        assert_eq!(names.len(), 4);
+        for name in ["collection.anki2", "media", "0", "1"] {
+            assert!(names.contains(&name), "missing {name}");
+        }
+
+        let mut media = String::new();
+        zip.by_name("media")
+            .unwrap()
+            .read_to_string(&mut media)
+            .unwrap();
+        assert_eq!(media, r#"{"0":"netthinne-0.png","1":"netthinne-1.png"}"#);
+
+        let mut database = Vec::new();
+        zip.by_name("collection.anki2")
+            .unwrap()
+            .read_to_end(&mut database)
+            .unwrap();
+        assert!(database.starts_with(b"SQLite format 3\0"));
+
+        let db = open_checked(&database, "apkg");
+        let mut notes = db
+            .prepare("SELECT guid, flds, sfld, csum FROM notes ORDER BY id")
+            .unwrap();
+        let notes = notes
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(notes.len(), 2);
+        for (guid, fields, sort_field, csum) in &notes {
+            assert_eq!(guid, &format!("netthinne:{sort_field}"));
+            assert!(fields.starts_with(&format!("{sort_field}{FIELD_SEPARATOR}")));
+            assert_eq!(*csum, checksum(sort_field));
+        }
+
+        let mut rows = db
+            .prepare("SELECT nid, ord, type, queue FROM cards ORDER BY nid, ord")
+            .unwrap();
+        let rows = rows
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows.len(), 4);
+        // Only the reviewed card has left the new queue.
+        assert_eq!(rows.iter().filter(|&&(.., kind, _)| kind != 0).count(), 1);
+
+        let lookup = "SELECT sfld FROM notes WHERE csum = ?1";
+        assert!(query_plan(&db, lookup).contains("ix_notes_csum"));
+        let word: String = db
+            .query_row(lookup, [checksum("bicycle")], |row| row.get(0))
+            .unwrap();
+        assert_eq!(word, "bicycle");
+        let nid = "SELECT ord FROM cards WHERE nid = ?1";
+        assert!(query_plan(&db, nid).contains("ix_cards_nid"));
+    }
+
+    #[test]
+    fn checksum_matches_anki() {
+        // int(sha1("bicycle").hexdigest()[:8], 16)
+        assert_eq!(checksum("bicycle"), 2_749_575_563);
+        assert_eq!(data_url_bytes("data:text/plain;base64,aGk="), None);
+        assert_eq!(
+            data_url_bytes("data:image/jpeg;base64,aGk="),
+            Some(("jpg", b"hi".to_vec()))
+        );
+    }
+}
@@ -0,0 +1,53 @@
+//! Plain CSV of the deck's word pairs, for spreadsheets and other flashcard apps.
+
+use super::group_words;
+use crate::deck::Card;
+
+/// One `english,norwegian` row per word under a header row, CRLF-terminated
+/// as RFC 4180 asks.
+pub fn build_csv(cards: &[Card]) -> String {
+    let mut out = String::from("english,norwegian\r\n");
+    for word in group_words(cards) {
+        out.push_str(&format!(
+            "{},{}\r\n",
+            field(word.label_en),
+            field(word.label_no)
+        ));
+    }
+    out
+}
+
+/// Quotes a field when it contains a comma, quote or line break.
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::{Direction, Schedule};
+
+    #[test]
+    fn one_row_per_word_with_quoting() {
+        let card = |label_en: &str, label_no: &str, direction| Card {
+            label_en: label_en.into(),
+            label_no: label_no.into(),
+            image_data_url: String::new(),
+            direction,
+            schedule: Schedule::new(0.0),
+        };
+        let cards = [
+            card("cat", "katt", Direction::EnglishToNorwegian),
+            card("cat", "katt", Direction::NorwegianToEnglish),
+            card("tie, bow", "sløyfe \"fin\"", Direction::EnglishToNorwegian),
+        ];
+        assert_eq!(
+            build_csv(&cards),
+            "english,norwegian\r\ncat,katt\r\n\"tie, bow\",\"sløyfe \"\"fin\"\"\"\r\n"
+        );
+    }
+}
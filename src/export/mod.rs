@@ -0,0 +1,98 @@
+//! Exports the flashcard deck for use outside the app, as an Anki package or a
+//! plain CSV file. Both are built in the browser and offered as a download.
+
+mod anki;
+mod csv;
+mod sqlite;
+
+use std::collections::BTreeMap;
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{window, Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+use crate::deck::{self, Card};
+use crate::error::{js_error, NetthinneError};
+
+/// How long the download's object URL stays valid. Some browsers (Safari, Firefox)
+/// start reading it only after `click()` returns.
+const REVOKE_DELAY_MS: i32 = 60_000;
+
+/// Downloads the deck as `netthinne.apkg`, pictures and schedules included.
+pub async fn export_apkg() -> Result<(), NetthinneError> {
+    let cards = deck::load_cards().await?;
+    let bytes = anki::build_apkg(&cards, js_sys::Date::now())?;
+    download(&bytes, "netthinne.apkg", "application/octet-stream")
+}
+
+/// Downloads the deck's English/Norwegian word pairs as `netthinne.csv`.
+pub async fn export_csv() -> Result<(), NetthinneError> {
+    let cards = deck::load_cards().await?;
+    let text = csv::build_csv(&cards);
+    download(text.as_bytes(), "netthinne.csv", "text/csv;charset=utf-8")
+}
+
+/// One word of the deck with its cards, one per review direction.
+struct Word<'a> {
+    label_en: &'a str,
+    label_no: &'a str,
+    image_data_url: &'a str,
+    cards: Vec<&'a Card>,
+}
+
+/// Groups the cards of each word, in alphabetical order of the English label.
+fn group_words(cards: &[Card]) -> Vec<Word<'_>> {
+    let mut words: BTreeMap<&str, Word> = BTreeMap::new();
+    for card in cards {
+        words
+            .entry(&card.label_en)
+            .or_insert_with(|| Word {
+                label_en: &card.label_en,
+                label_no: &card.label_no,
+                image_data_url: &card.image_data_url,
+                cards: Vec::new(),
+            })
+            .cards
+            .push(card);
+    }
+    words.into_values().collect()
+}
+
+/// Saves `bytes` through a temporary object URL and a clicked download link.
+fn download(bytes: &[u8], filename: &str, mime: &str) -> Result<(), NetthinneError> {
+    let failed = |what: &str, e: &wasm_bindgen::JsValue| {
+        NetthinneError::Export(format!("{what} failed: {}", js_error(e)))
+    };
+    let options = BlobPropertyBag::new();
+    options.set_type(mime);
+    let blob = Blob::new_with_u8_array_sequence_and_options(
+        &js_sys::Array::of1(&js_sys::Uint8Array::from(bytes)),
+        &options,
+    )
+    .map_err(|e| failed("new Blob", &e))?;
+    let url = Url::create_object_url_with_blob(&blob).map_err(|e| failed("createObjectURL", &e))?;
+
+    let window = window().ok_or_else(|| NetthinneError::Export("no window".into()))?;
+    let document = window
+        .document()
+        .ok_or_else(|| NetthinneError::Export("no document".into()))?;
+    let anchor: HtmlAnchorElement = document
+        .create_element("a")
+        .map_err(|e| failed("createElement", &e))?
+        .unchecked_into();
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+
+    let revoke = Closure::once_into_js(move || {
+        if let Err(e) = Url::revoke_object_url(&url) {
+            log::warn!("revokeObjectURL failed: {}", js_error(&e));
+        }
+    });
+    window
+        .set_timeout_with_callback_and_timeout_and_arguments_0(
+            revoke.unchecked_ref(),
+            REVOKE_DELAY_MS,
+        )
+        .map_err(|e| failed("setTimeout", &e))?;
+    Ok(())
+}
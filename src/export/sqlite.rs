@@ -0,0 +1,613 @@
+//! Minimal writer for the SQLite 3 file format, enough to produce the
+//! `collection.anki2` database inside an `.apkg` without a native SQLite.
+//!
+//! The whole database is built in one pass from rows known up front: every
+//! b-tree is bulk-loaded bottom up and nothing is ever updated in place.
+//!
+//! Limits, all of which the Anki schema stays within:
+//! - 4096-byte pages, UTF-8 text, rollback-journal mode and no free pages.
+//! - Values are NULL, 64-bit integers or text; no reals or blobs.
+//! - Ordinary rowid tables only. `integer primary key` columns are stored as
+//!   the rowid; `WITHOUT ROWID`, `UNIQUE` and other primary keys, which need
+//!   automatic indexes, are not supported.
+//! - Indexes compare text bytewise, SQLite's default `BINARY` collation.
+//! - The schema table must fit on the first page, so a database holds at most
+//!   a few dozen tables and indexes; `write_database` returns an error otherwise.
+//!
+//! Tests open the output with SQLite itself to check it.
+
+use std::cmp::Ordering;
+
+use crate::error::NetthinneError;
+
+const PAGE_SIZE: usize = 4096;
+const HEADER_SIZE: usize = 100;
+/// `SQLITE_VERSION_NUMBER` recorded as the last writer.
+const WRITER_VERSION: u32 = 3_045_000;
+
+/// Largest payload kept whole on a table leaf or index page; see "Cell Payload
+/// Overflow Pages" in the file format documentation.
+const TABLE_MAX_LOCAL: usize = PAGE_SIZE - 35;
+const INDEX_MAX_LOCAL: usize = (PAGE_SIZE - 12) * 64 / 255 - 23;
+const MIN_LOCAL: usize = (PAGE_SIZE - 12) * 32 / 255 - 23;
+
+const LEAF_TABLE: u8 = 0x0d;
+const INTERIOR_TABLE: u8 = 0x05;
+const LEAF_INDEX: u8 = 0x0a;
+const INTERIOR_INDEX: u8 = 0x02;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Text(String),
+}
+
+impl From<i64> for Value {
+    fn from(v: i64) -> Self {
+        Value::Integer(v)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::Text(v.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::Text(v)
+    }
+}
+
+/// A table and its rows, keyed by rowid. For an `integer primary key` table the
+/// key column must be `Value::Null` in `values`, as SQLite stores it as the rowid.
+pub struct Table {
+    pub name: &'static str,
+    pub sql: &'static str,
+    pub rows: Vec<(i64, Vec<Value>)>,
+}
+
+/// An index over columns of a table in the same database.
+pub struct Index {
+    pub name: &'static str,
+    pub table: &'static str,
+    pub sql: &'static str,
+    /// Positions of the indexed columns in the table's rows.
+    pub columns: &'static [usize],
+}
+
+/// Serializes `tables` and `indexes` into a complete database file.
+pub fn write_database(tables: &[Table], indexes: &[Index]) -> Result<Vec<u8>, NetthinneError> {
+    let mut pager = Pager::default();
+    // Page 1 holds the file header and the schema table, so reserve it first.
+    pager.alloc();
+
+    let mut schema = Vec::new();
+    for table in tables {
+        let root = write_table_tree(&mut pager, &table.rows);
+        schema.push(vec![
+            "table".into(),
+            table.name.into(),
+            table.name.into(),
+            Value::Integer(root as i64),
+            table.sql.into(),
+        ]);
+    }
+    for index in indexes {
+        let rows = tables
+            .iter()
+            .find(|t| t.name == index.table)
+            .map_or(&[][..], |t| &t.rows[..]);
+        let mut keys: Vec<Vec<Value>> = rows
+            .iter()
+            .map(|(rowid, values)| {
+                let mut key: Vec<Value> =
+                    index.columns.iter().map(|&c| values[c].clone()).collect();
+                key.push(Value::Integer(*rowid));
+                key
+            })
+            .collect();
+        keys.sort_by(|a, b| compare_keys(a, b));
+        let root = write_index_tree(&mut pager, &keys);
+        schema.push(vec![
+            "index".into(),
+            index.name.into(),
+            index.table.into(),
+            Value::Integer(root as i64),
+            index.sql.into(),
+        ]);
+    }
+
+    let cells: Vec<Vec<u8>> = schema
+        .iter()
+        .enumerate()
+        .map(|(i, row)| table_leaf_cell(&mut pager, i as i64 + 1, &encode_record(row)))
+        .collect();
+    if !fits(HEADER_SIZE, LEAF_TABLE, &cells) {
+        return Err(NetthinneError::Export(format!(
+            "the schema of {} tables and {} indexes does not fit on the first page",
+            tables.len(),
+            indexes.len()
+        )));
+    }
+    let page = build_page(HEADER_SIZE, LEAF_TABLE, &cells, None);
+    pager.pages[0] = page;
+    write_file_header(&mut pager);
+
+    Ok(pager.pages.concat())
+}
+
+#[derive(Default)]
+struct Pager {
+    pages: Vec<Vec<u8>>,
+}
+
+impl Pager {
+    /// Appends an empty page and returns its 1-based number.
+    fn alloc(&mut self) -> u32 {
+        self.pages.push(vec![0; PAGE_SIZE]);
+        self.pages.len() as u32
+    }
+
+    fn put(&mut self, page: Vec<u8>) -> u32 {
+        let number = self.alloc();
+        self.pages[number as usize - 1] = page;
+        number
+    }
+}
+
+fn write_file_header(pager: &mut Pager) {
+    let page_count = pager.pages.len() as u32;
+    let h = &mut pager.pages[0][..HEADER_SIZE];
+    h[..16].copy_from_slice(b"SQLite format 3\0");
+    h[16..18].copy_from_slice(&(PAGE_SIZE as u16).to_be_bytes());
+    // Legacy (rollback journal) read and write versions.
+    h[18] = 1;
+    h[19] = 1;
+    // Payload fractions, fixed by the file format.
+    h[21] = 64;
+    h[22] = 32;
+    h[23] = 32;
+    h[24..28].copy_from_slice(&1u32.to_be_bytes()); // file change counter
+    h[28..32].copy_from_slice(&page_count.to_be_bytes());
+    h[40..44].copy_from_slice(&1u32.to_be_bytes()); // schema cookie
+    h[44..48].copy_from_slice(&4u32.to_be_bytes()); // schema format
+    h[56..60].copy_from_slice(&1u32.to_be_bytes()); // UTF-8
+    h[92..96].copy_from_slice(&1u32.to_be_bytes()); // version-valid-for
+    h[96..100].copy_from_slice(&WRITER_VERSION.to_be_bytes());
+}
+
+/// Bulk-loads a table b-tree and returns its root page. Rows must have
+/// distinct rowids; they are sorted here.
+fn write_table_tree(pager: &mut Pager, rows: &[(i64, Vec<Value>)]) -> u32 {
+    let mut sorted: Vec<&(i64, Vec<Value>)> = rows.iter().collect();
+    sorted.sort_by_key(|(rowid, _)| *rowid);
+
+    // (page, largest rowid under it) for the level being built.
+    let mut level: Vec<(u32, i64)> = Vec::new();
+    let mut cells = Vec::new();
+    let mut last = 0;
+    for (rowid, values) in sorted {
+        let cell = table_leaf_cell(pager, *rowid, &encode_record(values));
+        cells.push(cell);
+        if !fits(0, LEAF_TABLE, &cells) {
+            let cell = cells.pop().unwrap();
+            let page = pager.put(build_page(0, LEAF_TABLE, &cells, None));
+            level.push((page, last));
+            cells = vec![cell];
+        }
+        last = *rowid;
+    }
+    let page = pager.put(build_page(0, LEAF_TABLE, &cells, None));
+    level.push((page, last));
+
+    // An interior cell is a child page number and a rowid varint.
+    let fanout = (PAGE_SIZE - page_header_size(INTERIOR_TABLE)) / (4 + 9 + 2) + 1;
+    while level.len() > 1 {
+        level = split_evenly(&level, fanout)
+            .into_iter()
+            .map(|group| {
+                let (right, max_rowid) = group[group.len() - 1];
+                let cells: Vec<Vec<u8>> = group[..group.len() - 1]
+                    .iter()
+                    .map(|(child, key)| {
+                        let mut cell = child.to_be_bytes().to_vec();
+                        put_varint(&mut cell, *key as u64);
+                        cell
+                    })
+                    .collect();
+                let page = pager.put(build_page(0, INTERIOR_TABLE, &cells, Some(right)));
+                (page, max_rowid)
+            })
+            .collect();
+    }
+    level[0].0
+}
+
+/// Bulk-loads an index b-tree from sorted keys and returns its root page.
+/// Unlike table trees, every key lives on exactly one page, interior or leaf.
+fn write_index_tree(pager: &mut Pager, keys: &[Vec<Value>]) -> u32 {
+    // Pack leaves; the key after each full leaf moves up as a separator.
+    let mut children = Vec::new();
+    let mut separators = Vec::new();
+    let mut cells = Vec::new();
+    let mut used = page_header_size(LEAF_INDEX);
+    for payload in keys.iter().map(|k| encode_record(k)) {
+        // Sized before building, so a separator never leaves orphaned overflow pages.
+        let size = index_cell_len(payload.len()) + 2;
+        if used + size > PAGE_SIZE {
+            children.push(pager.put(build_page(0, LEAF_INDEX, &cells, None)));
+            cells = Vec::new();
+            used = page_header_size(LEAF_INDEX);
+            separators.push(payload);
+            continue;
+        }
+        cells.push(index_leaf_cell(pager, &payload));
+        used += size;
+    }
+    // Never leave the last leaf empty: take its separator back instead.
+    if cells.is_empty() {
+        if let Some(payload) = separators.pop() {
+            cells.push(index_leaf_cell(pager, &payload));
+        }
+    }
+    children.push(pager.put(build_page(0, LEAF_INDEX, &cells, None)));
+
+    // Children of a page sit between separators, so a group of n children
+    // takes n - 1 separators and the one after it moves up a level.
+    let largest = separators.iter().map(Vec::len).max().unwrap_or(0);
+    let max_cell = 4 + index_cell_len(largest) + 2;
+    let fanout = (PAGE_SIZE - page_header_size(INTERIOR_INDEX)) / max_cell + 1;
+    while children.len() > 1 {
+        let groups = split_evenly(&children, fanout);
+        let mut separators_iter = separators.into_iter();
+        let mut next_children = Vec::new();
+        let mut next_separators = Vec::new();
+        for (i, group) in groups.iter().enumerate() {
+            let cells: Vec<Vec<u8>> = group[..group.len() - 1]
+                .iter()
+                .map(|&child| {
+                    let separator = separators_iter.next().unwrap();
+                    index_interior_cell(pager, child, &separator)
+                })
+                .collect();
+            let right = group[group.len() - 1];
+            next_children.push(pager.put(build_page(0, INTERIOR_INDEX, &cells, Some(right))));
+            if i + 1 < groups.len() {
+                next_separators.push(separators_iter.next().unwrap());
+            }
+        }
+        children = next_children;
+        separators = next_separators;
+    }
+    children[0]
+}
+
+/// Splits `items` into as few groups of at most `max` as possible, with sizes
+/// differing by at most one so no interior page is left nearly empty.
+fn split_evenly<T: Copy>(items: &[T], max: usize) -> Vec<Vec<T>> {
+    let groups = items.len().div_ceil(max);
+    let base = items.len() / groups;
+    let extra = items.len() % groups;
+    let mut rest = items;
+    (0..groups)
+        .map(|i| {
+            let (group, tail) = rest.split_at(base + usize::from(i < extra));
+            rest = tail;
+            group.to_vec()
+        })
+        .collect()
+}
+
+fn table_leaf_cell(pager: &mut Pager, rowid: i64, payload: &[u8]) -> Vec<u8> {
+    let mut cell = Vec::new();
+    put_varint(&mut cell, payload.len() as u64);
+    put_varint(&mut cell, rowid as u64);
+    let local = local_payload(payload.len(), TABLE_MAX_LOCAL);
+    append_payload(pager, &mut cell, payload, local);
+    cell
+}
+
+fn index_leaf_cell(pager: &mut Pager, payload: &[u8]) -> Vec<u8> {
+    let mut cell = Vec::new();
+    put_varint(&mut cell, payload.len() as u64);
+    let local = local_payload(payload.len(), INDEX_MAX_LOCAL);
+    append_payload(pager, &mut cell, payload, local);
+    cell
+}
+
+/// Size of an index cell for a `len`-byte payload, without the child pointer.
+fn index_cell_len(len: usize) -> usize {
+    let local = local_payload(len, INDEX_MAX_LOCAL);
+    let overflow = if local < len { 4 } else { 0 };
+    varint_len(len as u64) + local + overflow
+}
+
+fn index_interior_cell(pager: &mut Pager, child: u32, payload: &[u8]) -> Vec<u8> {
+    let mut cell = child.to_be_bytes().to_vec();
+    cell.extend(index_leaf_cell(pager, payload));
+    cell
+}
+
+/// Bytes of a `len`-byte payload kept on the b-tree page, given the page
+/// type's maximum local payload `max_local`. The rest spills to overflow pages.
+fn local_payload(len: usize, max_local: usize) -> usize {
+    if len <= max_local {
+        return len;
+    }
+    let k = MIN_LOCAL + (len - MIN_LOCAL) % (PAGE_SIZE - 4);
+    if k <= max_local {
+        k
+    } else {
+        MIN_LOCAL
+    }
+}
+
+/// Appends the first `local` bytes of `payload` to `cell` and chains the
+/// remainder through overflow pages.
+fn append_payload(pager: &mut Pager, cell: &mut Vec<u8>, payload: &[u8], local: usize) {
+    cell.extend_from_slice(&payload[..local]);
+    if local == payload.len() {
+        return;
+    }
+    let chunks: Vec<&[u8]> = payload[local..].chunks(PAGE_SIZE - 4).collect();
+    let first = pager.pages.len() as u32 + 1;
+    for (i, chunk) in chunks.iter().enumerate() {
+        let next = if i + 1 < chunks.len() {
+            first + i as u32 + 1
+        } else {
+            0
+        };
+        let mut page = vec![0; PAGE_SIZE];
+        page[..4].copy_from_slice(&next.to_be_bytes());
+        page[4..4 + chunk.len()].copy_from_slice(chunk);
+        pager.put(page);
+    }
+    cell.extend_from_slice(&first.to_be_bytes());
+}
+
+fn page_header_size(kind: u8) -> usize {
+    match kind {
+        INTERIOR_TABLE | INTERIOR_INDEX => 12,
+        _ => 8,
+    }
+}
+
+/// Whether `cells` fit on a page whose b-tree header starts at `offset`.
+fn fits(offset: usize, kind: u8, cells: &[Vec<u8>]) -> bool {
+    let used: usize = cells.iter().map(|c| c.len() + 2).sum();
+    offset + page_header_size(kind) + used <= PAGE_SIZE
+}
+
+/// Lays out one b-tree page: header, cell pointer array, then the cells packed
+/// against the end of the page.
+fn build_page(offset: usize, kind: u8, cells: &[Vec<u8>], right_child: Option<u32>) -> Vec<u8> {
+    let mut page = vec![0; PAGE_SIZE];
+    let mut content = PAGE_SIZE;
+    let pointers = offset + page_header_size(kind);
+    for (i, cell) in cells.iter().enumerate() {
+        content -= cell.len();
+        page[content..content + cell.len()].copy_from_slice(cell);
+        page[pointers + 2 * i..pointers + 2 * i + 2]
+            .copy_from_slice(&(content as u16).to_be_bytes());
+    }
+    page[offset] = kind;
+    page[offset + 3..offset + 5].copy_from_slice(&(cells.len() as u16).to_be_bytes());
+    // A content start of 65536 is stored as 0, which never happens with 4 KiB pages.
+    page[offset + 5..offset + 7].copy_from_slice(&(content as u16).to_be_bytes());
+    if let Some(child) = right_child {
+        page[offset + 8..offset + 12].copy_from_slice(&child.to_be_bytes());
+    }
+    page
+}
+
+/// Encodes values in the SQLite record format: a header of serial types
+/// followed by the values themselves.
+fn encode_record(values: &[Value]) -> Vec<u8> {
+    let mut types = Vec::new();
+    let mut body = Vec::new();
+    for value in values {
+        match value {
+            Value::Null => put_varint(&mut types, 0),
+            Value::Integer(0) => put_varint(&mut types, 8),
+            Value::Integer(1) => put_varint(&mut types, 9),
+            Value::Integer(v) => {
+                let (serial, width) = match *v {
+                    -0x80..=0x7f => (1, 1),
+                    -0x8000..=0x7fff => (2, 2),
+                    -0x80_0000..=0x7f_ffff => (3, 3),
+                    -0x8000_0000..=0x7fff_ffff => (4, 4),
+                    -0x8000_0000_0000..=0x7fff_ffff_ffff => (5, 6),
+                    _ => (6, 8),
+                };
+                put_varint(&mut types, serial);
+                body.extend_from_slice(&v.to_be_bytes()[8 - width..]);
+            }
+            Value::Text(s) => {
+                put_varint(&mut types, 13 + 2 * s.len() as u64);
+                body.extend_from_slice(s.as_bytes());
+            }
+        }
+    }
+    // The header length counts its own varint.
+    let mut header_len = types.len() + 1;
+    if varint_len(header_len as u64) > 1 {
+        header_len = types.len() + varint_len((types.len() + 2) as u64);
+    }
+    let mut record = Vec::with_capacity(header_len + body.len());
+    put_varint(&mut record, header_len as u64);
+    record.extend(types);
+    record.extend(body);
+    record
+}
+
+/// SQLite's big-endian variable-length integer, 1 to 9 bytes.
+fn put_varint(out: &mut Vec<u8>, v: u64) {
+    if v > 0x00ff_ffff_ffff_ffff {
+        for i in (1..9).rev() {
+            out.push(((v >> (8 + 7 * (i - 1))) as u8 & 0x7f) | 0x80);
+        }
+        out.push(v as u8);
+        return;
+    }
+    let len = varint_len(v);
+    for i in (0..len).rev() {
+        let byte = ((v >> (7 * i)) & 0x7f) as u8;
+        out.push(if i == 0 { byte } else { byte | 0x80 });
+    }
+}
+
+fn varint_len(v: u64) -> usize {
+    if v > 0x00ff_ffff_ffff_ffff {
+        return 9;
+    }
+    (1..9).find(|&n| v >> (7 * n) == 0).unwrap_or(9)
+}
+
+/// Orders index keys the way SQLite does with the default BINARY collation.
+fn compare_keys(a: &[Value], b: &[Value]) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Integer(_) => 1,
+            Value::Text(_) => 2,
+        }
+    }
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| match (x, y) {
+            (Value::Integer(x), Value::Integer(y)) => x.cmp(y),
+            (Value::Text(x), Value::Text(y)) => x.as_bytes().cmp(y.as_bytes()),
+            _ => rank(x).cmp(&rank(y)),
+        })
+        .find(|o| o.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use std::{env, fs, process};
+
+    use rusqlite::Connection;
+
+    use super::*;
+
+    /// Opens `file` with SQLite itself and checks it passes `PRAGMA integrity_check`.
+    pub fn open_checked(file: &[u8], name: &str) -> Connection {
+        let path = env::temp_dir().join(format!("netthinne-{name}-{}.sqlite", process::id()));
+        fs::write(&path, file).unwrap();
+        let db = Connection::open(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let check: String = db
+            .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(check, "ok");
+        db
+    }
+
+    /// SQLite's plan for `sql`, one detail line per step. Parameters stay unbound.
+    pub fn query_plan(db: &Connection, sql: &str) -> String {
+        let mut plan = db.prepare(&format!("EXPLAIN QUERY PLAN {sql}")).unwrap();
+        let mut rows = plan.raw_query();
+        let mut steps = Vec::new();
+        while let Some(row) = rows.next().unwrap() {
+            steps.push(row.get::<_, String>(3).unwrap());
+        }
+        steps.join("\n")
+    }
+
+    fn varint(v: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        put_varint(&mut out, v);
+        out
+    }
+
+    #[test]
+    fn varints_match_the_file_format() {
+        assert_eq!(varint(0), [0x00]);
+        assert_eq!(varint(127), [0x7f]);
+        assert_eq!(varint(128), [0x81, 0x00]);
+        assert_eq!(varint(16_383), [0xff, 0x7f]);
+        assert_eq!(varint(16_384), [0x81, 0x80, 0x00]);
+        // The ninth byte carries a full 8 bits.
+        assert_eq!(varint(u64::MAX), [0xff; 9]);
+        assert_eq!(varint((-1i64) as u64).len(), 9);
+    }
+
+    #[test]
+    fn records_use_compact_serial_types() {
+        let record = encode_record(&[
+            Value::Null,
+            Value::Integer(0),
+            Value::Integer(1),
+            Value::Integer(300),
+            Value::Integer(-2),
+            "hi".into(),
+        ]);
+        // Header: its own length, then NULL, 0, 1, i16, i8 and a 2-byte text.
+        assert_eq!(record[..7], [7, 0, 8, 9, 2, 1, 17]);
+        assert_eq!(record[7..], [0x01, 0x2c, 0xfe, b'h', b'i']);
+    }
+
+    #[test]
+    fn large_tables_span_pages_and_header_counts_them() {
+        let rows = (1..=2000)
+            .map(|i| (i, vec![Value::Null, Value::Text(format!("row {i}"))]))
+            .collect();
+        let tables = [Table {
+            name: "t",
+            sql: "CREATE TABLE t (id integer primary key, s text)",
+            rows,
+        }];
+        let indexes = [Index {
+            name: "ix_t_s",
+            table: "t",
+            sql: "CREATE INDEX ix_t_s on t (s)",
+            columns: &[1],
+        }];
+        let file = write_database(&tables, &indexes).unwrap();
+
+        assert!(file.starts_with(b"SQLite format 3\0"));
+        assert_eq!(file.len() % PAGE_SIZE, 0);
+        let pages = u32::from_be_bytes(file[28..32].try_into().unwrap()) as usize;
+        assert_eq!(pages, file.len() / PAGE_SIZE);
+        assert!(pages > 4);
+        // Page 1 is the schema leaf, right after the file header.
+        assert_eq!(file[HEADER_SIZE], LEAF_TABLE);
+
+        let db = open_checked(&file, "large-table");
+        let count: i64 = db
+            .query_row("SELECT count(*) FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 2000);
+        let s: String = db
+            .query_row("SELECT s FROM t WHERE id = 1500", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(s, "row 1500");
+
+        let lookup = "SELECT id FROM t WHERE s = ?1";
+        assert!(query_plan(&db, lookup).contains("ix_t_s"));
+        let id: i64 = db
+            .query_row(lookup, ["row 1234"], |row| row.get(0))
+            .unwrap();
+        assert_eq!(id, 1234);
+    }
+
+    #[test]
+    fn oversized_schema_is_an_error() {
+        // Two long definitions cannot share the first page with the file header.
+        let sql = |name: &str| format!("CREATE TABLE {name} (s text) -- {}", "x".repeat(3000));
+        let tables = ["a", "b"].map(|name| Table {
+            name,
+            sql: sql(name).leak(),
+            rows: Vec::new(),
+        });
+        assert!(matches!(
+            write_database(&tables, &[]),
+            Err(NetthinneError::Export(_))
+        ));
+        assert!(write_database(&tables[..1], &[]).is_ok());
+    }
+}
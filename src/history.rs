@@ -0,0 +1,166 @@
+//! Past scans kept in IndexedDB so results survive "New Scan" and reloads.
+//!
+//! Each scan is stored as a JSON string under an auto-incremented key, so key
+//! order is capture order and eviction simply drops the lowest keys.
+//! Eviction caps both the number of scans and their total encoded size, since
+//! the browser's storage quota is shared with the deck.
+
+use js_sys::Array;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{IdbObjectStore, IdbTransactionMode};
+
+use netthinne_core::ml::DetectedObject;
+
+use crate::db::{self, failed, wait, SCANS_STORE};
+use crate::error::NetthinneError;
+
+/// Oldest scans are evicted beyond this many, keeping storage use bounded.
+pub const MAX_SCANS: usize = 50;
+/// Oldest scans are also evicted once the stored JSON exceeds this many bytes.
+pub const MAX_BYTES: usize = 8 * 1024 * 1024;
+/// Longest side of the stored frame thumbnail.
+pub const THUMBNAIL_SIDE: u32 = 320;
+/// Longest side of each stored detection crop.
+pub const CROP_THUMBNAIL_SIDE: u32 = 160;
+
+/// One saved scan.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scan {
+    /// IndexedDB key; assigned on save and not part of the stored value.
+    #[serde(skip)]
+    pub id: u32,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: f64,
+    /// Downscaled JPEG data URL of the captured frame.
+    pub thumbnail_url: String,
+    /// Size of the original frame, which detection boxes refer to.
+    pub width: u32,
+    pub height: u32,
+    pub detections: Vec<DetectedObject>,
+}
+
+/// Stores `scan`, evicting the oldest scans beyond `MAX_SCANS` or `MAX_BYTES`.
+/// Returns the new id.
+pub async fn save_scan(scan: &Scan) -> Result<u32, NetthinneError> {
+    let json = serde_json::to_string(scan)
+        .map_err(|e| NetthinneError::Storage(format!("failed to encode scan: {e}")))?;
+    let store = scans_store(IdbTransactionMode::Readwrite).await?;
+    let key = wait(
+        &store
+            .add(&json.into())
+            .map_err(|e| failed("add scan", &e))?,
+    )
+    .await?;
+
+    if let Err(e) = evict_oldest().await {
+        log::warn!("History eviction failed: {e}");
+    }
+    key.as_f64()
+        .map(|k| k as u32)
+        .ok_or_else(|| NetthinneError::Storage("scan key is not a number".into()))
+}
+
+/// All stored scans, newest first. Entries that no longer parse are skipped.
+pub async fn list_scans() -> Result<Vec<Scan>, NetthinneError> {
+    let store = scans_store(IdbTransactionMode::Readonly).await?;
+    let keys: Array = wait(&store.get_all_keys().map_err(|e| failed("list keys", &e))?)
+        .await?
+        .unchecked_into();
+    let values: Array = wait(&store.get_all().map_err(|e| failed("list scans", &e))?)
+        .await?
+        .unchecked_into();
+
+    let mut scans: Vec<Scan> = keys
+        .iter()
+        .zip(values.iter())
+        .filter_map(|(key, value)| {
+            let mut scan: Scan = match serde_json::from_str(&value.as_string()?) {
+                Ok(scan) => scan,
+                Err(e) => {
+                    log::warn!("Skipping unreadable scan {key:?}: {e}");
+                    return None;
+                }
+            };
+            scan.id = key.as_f64()? as u32;
+            Some(scan)
+        })
+        .collect();
+    scans.reverse();
+    Ok(scans)
+}
+
+/// Removes one scan.
+pub async fn delete_scan(id: u32) -> Result<(), NetthinneError> {
+    let store = scans_store(IdbTransactionMode::Readwrite).await?;
+    wait(
+        &store
+            .delete(&id.into())
+            .map_err(|e| failed("delete scan", &e))?,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Deletes the lowest-keyed (oldest) scans until the rest fit `MAX_SCANS` and `MAX_BYTES`.
+async fn evict_oldest() -> Result<(), NetthinneError> {
+    let store = scans_store(IdbTransactionMode::Readwrite).await?;
+    let keys: Array = wait(&store.get_all_keys().map_err(|e| failed("list keys", &e))?)
+        .await?
+        .unchecked_into();
+    let values: Array = wait(&store.get_all().map_err(|e| failed("list scans", &e))?)
+        .await?
+        .unchecked_into();
+    let sizes: Vec<usize> = values
+        .iter()
+        .map(|value| value.as_string().map_or(0, |json| json.len()))
+        .collect();
+    let excess = excess_scans(&sizes, MAX_SCANS, MAX_BYTES);
+    // Requests are queued without awaiting so they share one transaction.
+    let requests = keys
+        .iter()
+        .take(excess)
+        .map(|key| store.delete(&key).map_err(|e| failed("evict scan", &e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    for request in requests {
+        wait(&request).await?;
+    }
+    Ok(())
+}
+
+/// How many of the oldest scans to drop so at most `max_count` remain and their
+/// sizes sum to at most `max_bytes`. `sizes` is oldest first; the newest scan is
+/// always kept.
+fn excess_scans(sizes: &[usize], max_count: usize, max_bytes: usize) -> usize {
+    let mut total = 0;
+    let kept = sizes
+        .iter()
+        .rev()
+        .take(max_count.max(1))
+        .enumerate()
+        .take_while(|&(i, &size)| {
+            total += size;
+            i == 0 || total <= max_bytes
+        })
+        .count();
+    sizes.len() - kept
+}
+
+async fn scans_store(mode: IdbTransactionMode) -> Result<IdbObjectStore, NetthinneError> {
+    db::store(SCANS_STORE, mode).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excess_scans_caps_count_and_size() {
+        assert_eq!(excess_scans(&[], 3, 100), 0);
+        assert_eq!(excess_scans(&[10, 10, 10, 10], 3, 100), 1);
+        assert_eq!(excess_scans(&[40, 40, 40], 3, 100), 1);
+        assert_eq!(excess_scans(&[10, 10, 10], 3, 30), 0);
+        // A single oversized scan is still kept.
+        assert_eq!(excess_scans(&[10, 500], 3, 100), 1);
+    }
+}
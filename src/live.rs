@@ -0,0 +1,109 @@
+//! Live detection overlay: YOLO boxes drawn over the camera preview.
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{window, CanvasRenderingContext2d, HtmlCanvasElement};
+
+use netthinne_core::ml::LabeledBox;
+
+use crate::error::{js_error, NetthinneError};
+
+/// Pause between sampled frames; detection on a phone takes a few hundred ms anyway.
+pub const FRAME_INTERVAL_MS: i32 = 400;
+/// Longest side of a sampled frame. YOLO letterboxes to 640, so more is wasted.
+pub const FRAME_MAX_SIDE: u32 = 640;
+
+const BOX_COLOR: &str = "#4fc3f7";
+const LABEL_TEXT_COLOR: &str = "#0d2f3a";
+const LABEL_FONT_PX: f64 = 16.0;
+const LABEL_PADDING: f64 = 4.0;
+
+/// Resolves after `ms` milliseconds.
+pub async fn sleep(ms: i32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        if let Some(window) = window() {
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms);
+        }
+    });
+    let _ = JsFuture::from(promise).await;
+}
+
+/// Redraws the overlay canvas with `boxes`, which are in pixels of a
+/// `width` x `height` frame. The canvas is stretched over the preview by CSS,
+/// so its backing size is set to the frame size and boxes are drawn unscaled.
+pub fn draw_boxes(
+    canvas_id: &str,
+    width: u32,
+    height: u32,
+    boxes: &[LabeledBox],
+) -> Result<(), NetthinneError> {
+    let (canvas, ctx) = overlay(canvas_id)?;
+    if canvas.width() != width || canvas.height() != height {
+        canvas.set_width(width);
+        canvas.set_height(height);
+    }
+    ctx.clear_rect(0.0, 0.0, width as f64, height as f64);
+
+    // Keep strokes and labels readable however small the sampled frame is.
+    let scale = (width.max(height) as f64 / FRAME_MAX_SIDE as f64).max(0.5);
+    ctx.set_line_width(2.0 * scale);
+    ctx.set_font(&format!("600 {}px sans-serif", LABEL_FONT_PX * scale));
+    ctx.set_text_baseline("bottom");
+
+    for b in boxes {
+        let [x1, y1, x2, y2] = b.bbox.map(f64::from);
+        ctx.set_stroke_style_str(BOX_COLOR);
+        ctx.stroke_rect(x1, y1, x2 - x1, y2 - y1);
+
+        let text = format!("{} / {}", b.label.label_en, b.label.label_no);
+        let text_width = ctx
+            .measure_text(&text)
+            .map_err(|e| NetthinneError::Camera(format!("measureText: {}", js_error(&e))))?
+            .width();
+        let label_height = (LABEL_FONT_PX + 2.0 * LABEL_PADDING) * scale;
+        // Labels sit above the box, or inside it when the box touches the top edge.
+        let label_top = if y1 >= label_height {
+            y1 - label_height
+        } else {
+            y1
+        };
+        ctx.set_fill_style_str(BOX_COLOR);
+        ctx.fill_rect(
+            x1,
+            label_top,
+            text_width + 2.0 * LABEL_PADDING * scale,
+            label_height,
+        );
+        ctx.set_fill_style_str(LABEL_TEXT_COLOR);
+        ctx.fill_text(
+            &text,
+            x1 + LABEL_PADDING * scale,
+            label_top + label_height - LABEL_PADDING * scale,
+        )
+        .map_err(|e| NetthinneError::Camera(format!("fillText: {}", js_error(&e))))?;
+    }
+    Ok(())
+}
+
+/// Removes every box from the overlay.
+pub fn clear(canvas_id: &str) -> Result<(), NetthinneError> {
+    let (canvas, ctx) = overlay(canvas_id)?;
+    ctx.clear_rect(0.0, 0.0, canvas.width() as f64, canvas.height() as f64);
+    Ok(())
+}
+
+fn overlay(
+    canvas_id: &str,
+) -> Result<(HtmlCanvasElement, CanvasRenderingContext2d), NetthinneError> {
+    let canvas: HtmlCanvasElement = window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(canvas_id))
+        .and_then(|el| el.dyn_into().ok())
+        .ok_or_else(|| NetthinneError::Camera(format!("no canvas #{canvas_id}")))?;
+    let ctx = canvas
+        .get_context("2d")
+        .map_err(|e| NetthinneError::Camera(format!("get 2d context: {}", js_error(&e))))?
+        .and_then(|c| c.dyn_into::<CanvasRenderingContext2d>().ok())
+        .ok_or_else(|| NetthinneError::Camera("no 2d context".into()))?;
+    Ok((canvas, ctx))
+}
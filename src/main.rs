@@ -1,10 +1,21 @@
 mod app;
 mod camera;
-mod image_utils;
-mod ml;
+mod db;
+mod deck;
+mod error;
+mod export;
+mod history;
+mod live;
+mod settings;
+mod speech;
 mod state;
+mod worker;
 
 fn main() {
     wasm_logger::init(wasm_logger::Config::default());
+    if worker::is_worker_scope() {
+        worker::run();
+        return;
+    }
     dioxus::launch(app::App);
 }
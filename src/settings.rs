@@ -0,0 +1,73 @@
+use web_sys::{window, Storage};
+
+use netthinne_core::ml::DetectionConfig;
+
+use crate::camera::CameraChoice;
+use crate::error::{js_error, NetthinneError};
+
+const CONF_THRESHOLD_KEY: &str = "netthinne.conf_threshold";
+const IOU_THRESHOLD_KEY: &str = "netthinne.iou_threshold";
+const MAX_DETECTIONS_KEY: &str = "netthinne.max_detections";
+const SUPPRESSION_KEY: &str = "netthinne.suppression";
+const CAMERA_KEY: &str = "netthinne.camera";
+
+/// Loads the detection settings from localStorage.
+/// Missing or unparsable values fall back to the defaults, and the rest are
+/// clamped to the ranges the settings screen offers.
+pub fn load_detection_config() -> DetectionConfig {
+    let defaults = DetectionConfig::default();
+    let Ok(storage) = local_storage() else {
+        return defaults;
+    };
+
+    DetectionConfig {
+        conf_threshold: read(&storage, CONF_THRESHOLD_KEY).unwrap_or(defaults.conf_threshold),
+        iou_threshold: read(&storage, IOU_THRESHOLD_KEY).unwrap_or(defaults.iou_threshold),
+        max_detections: read(&storage, MAX_DETECTIONS_KEY).unwrap_or(defaults.max_detections),
+        suppression: read(&storage, SUPPRESSION_KEY).unwrap_or(defaults.suppression),
+    }
+    .clamped()
+}
+
+/// Persists the detection settings to localStorage.
+pub fn save_detection_config(config: &DetectionConfig) -> Result<(), NetthinneError> {
+    let storage = local_storage()?;
+    write(&storage, CONF_THRESHOLD_KEY, config.conf_threshold)?;
+    write(&storage, IOU_THRESHOLD_KEY, config.iou_threshold)?;
+    write(&storage, MAX_DETECTIONS_KEY, config.max_detections)?;
+    write(&storage, SUPPRESSION_KEY, config.suppression)?;
+    Ok(())
+}
+
+/// Loads the last camera the user picked, defaulting to the back camera.
+pub fn load_camera_choice() -> CameraChoice {
+    local_storage()
+        .ok()
+        .and_then(|storage| read(&storage, CAMERA_KEY))
+        .unwrap_or_default()
+}
+
+/// Remembers the camera choice for the next visit.
+pub fn save_camera_choice(choice: &CameraChoice) -> Result<(), NetthinneError> {
+    write(&local_storage()?, CAMERA_KEY, choice)
+}
+
+fn local_storage() -> Result<Storage, NetthinneError> {
+    window()
+        .ok_or_else(|| NetthinneError::Storage("no window".into()))?
+        .local_storage()
+        .map_err(|e| {
+            NetthinneError::Storage(format!("localStorage unavailable: {}", js_error(&e)))
+        })?
+        .ok_or_else(|| NetthinneError::Storage("localStorage unavailable".into()))
+}
+
+fn read<T: std::str::FromStr>(storage: &Storage, key: &str) -> Option<T> {
+    storage.get_item(key).ok().flatten()?.parse().ok()
+}
+
+fn write<T: ToString>(storage: &Storage, key: &str, value: T) -> Result<(), NetthinneError> {
+    storage
+        .set_item(key, &value.to_string())
+        .map_err(|e| NetthinneError::Storage(format!("failed to store {key}: {}", js_error(&e))))
+}
@@ -0,0 +1,92 @@
+//! Pronunciation of Norwegian labels through the Web Speech API.
+
+use wasm_bindgen::JsCast;
+use web_sys::{window, SpeechSynthesis, SpeechSynthesisUtterance, SpeechSynthesisVoice};
+
+use crate::error::{js_error, NetthinneError};
+
+const LANG: &str = "nb-NO";
+/// Speaking rate for slow playback; 1.0 is the voice's normal speed.
+pub const SLOW_RATE: f32 = 0.6;
+
+/// Asks the browser for its voices early. Chrome fills the list asynchronously,
+/// and `speak` cannot wait for it without leaving the user gesture.
+pub fn preload_voices() {
+    match synthesis() {
+        Ok(synth) => {
+            synth.get_voices();
+        }
+        Err(e) => log::warn!("Speech synthesis unavailable: {e}"),
+    }
+}
+
+/// Reads `text` aloud with a Norwegian voice, replacing anything still playing.
+/// Must run synchronously inside the click handler: iOS only plays speech
+/// started from a user gesture. While the voice list is still loading, the
+/// utterance goes out with just the language tag and the browser picks a voice.
+pub fn speak(text: &str, slow: bool) -> Result<(), NetthinneError> {
+    let synth = synthesis()?;
+    let voice = norwegian_voice(&synth);
+    if voice.is_none() && synth.get_voices().length() > 0 {
+        return Err(NetthinneError::NoNorwegianVoice);
+    }
+
+    let utterance = SpeechSynthesisUtterance::new_with_text(text)
+        .map_err(|e| NetthinneError::Speech(js_error(&e)))?;
+    utterance.set_lang(LANG);
+    utterance.set_voice(voice.as_ref());
+    utterance.set_rate(if slow { SLOW_RATE } else { 1.0 });
+
+    synth.cancel();
+    synth.speak(&utterance);
+    Ok(())
+}
+
+fn synthesis() -> Result<SpeechSynthesis, NetthinneError> {
+    window()
+        .ok_or_else(|| NetthinneError::Speech("no window".into()))?
+        .speech_synthesis()
+        .map_err(|e| NetthinneError::Speech(format!("speech unavailable: {}", js_error(&e))))
+}
+
+/// The best installed Norwegian voice among those loaded so far.
+fn norwegian_voice(synth: &SpeechSynthesis) -> Option<SpeechSynthesisVoice> {
+    synth
+        .get_voices()
+        .iter()
+        .map(|v| v.unchecked_into::<SpeechSynthesisVoice>())
+        .filter_map(|voice| Some((voice_rank(&voice.lang())?, voice)))
+        .min_by_key(|(rank, _)| *rank)
+        .map(|(_, voice)| voice)
+}
+
+/// Preference for a voice language tag, lower is better: Bokmål, then the
+/// generic `no` tag some platforms use, then Nynorsk. `None` if not Norwegian.
+fn voice_rank(lang: &str) -> Option<u8> {
+    let lang = lang.to_ascii_lowercase().replace('_', "-");
+    let primary = lang.split('-').next().unwrap_or_default();
+    match primary {
+        "nb" if lang == "nb-no" => Some(0),
+        "nb" => Some(1),
+        "no" => Some(2),
+        "nn" => Some(3),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voice_rank_prefers_bokmal_and_skips_other_languages() {
+        assert_eq!(voice_rank("nb-NO"), Some(0));
+        assert_eq!(voice_rank("nb_NO"), Some(0));
+        assert_eq!(voice_rank("nb"), Some(1));
+        assert_eq!(voice_rank("no-NO"), Some(2));
+        assert_eq!(voice_rank("nn-NO"), Some(3));
+        assert_eq!(voice_rank("en-US"), None);
+        assert_eq!(voice_rank("nl-NL"), None);
+        assert_eq!(voice_rank(""), None);
+    }
+}
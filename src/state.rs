@@ -1,18 +1,34 @@
-use crate::ml::DetectedObject;
+use netthinne_core::ml::{DetectedObject, DetectionConfig};
+
+use crate::camera::{CameraChoice, CameraDevice};
+use crate::error::NetthinneError;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Screen {
     Camera,
     Processing,
     Result,
+    Settings,
+    History,
+    Review,
 }
 
 #[derive(Clone, Debug)]
 pub struct AppState {
     pub screen: Screen,
     pub detections: Vec<DetectedObject>,
-    pub error: Option<String>,
+    pub error: Option<NetthinneError>,
     pub captured_pixels: Option<(Vec<u8>, u32, u32)>,
+    /// Captured frame kept for the annotated result view: (data_url, width, height).
+    pub captured_image: Option<(String, u32, u32)>,
+    pub detection_config: DetectionConfig,
+    /// Set once both models are loaded; the shutter stays disabled until then.
+    pub models_ready: bool,
+    /// The inference worker did not start, so the models run on the main thread.
+    pub worker_failed: bool,
+    /// Video inputs found on this device; labels appear once permission is granted.
+    pub cameras: Vec<CameraDevice>,
+    pub camera_choice: CameraChoice,
 }
 
 impl Default for AppState {
@@ -22,6 +38,24 @@ impl Default for AppState {
             detections: Vec::new(),
             error: None,
             captured_pixels: None,
+            captured_image: None,
+            detection_config: DetectionConfig::default(),
+            models_ready: false,
+            worker_failed: false,
+            cameras: Vec::new(),
+            camera_choice: CameraChoice::default(),
         }
     }
 }
+
+impl AppState {
+    /// Clears the current scan and returns to the camera, keeping user settings,
+    /// model status and the camera list.
+    pub fn reset(&mut self) {
+        self.screen = Screen::Camera;
+        self.detections.clear();
+        self.error = None;
+        self.captured_pixels = None;
+        self.captured_image = None;
+    }
+}
@@ -0,0 +1,556 @@
+//! Runs the ML pipeline off the main thread.
+//!
+//! The worker runs the app's own wasm bundle. A small bootstrap module imports
+//! the wasm-bindgen glue and instantiates the module the page has already
+//! compiled; `main` then sees there is no `window` and calls [`run`] instead of
+//! launching the UI, which reports [`Response::Started`].
+//! Messages are `{ request: <json>, pixels?: Uint8Array }` towards the worker
+//! and a JSON [`Response`] string back.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use js_sys::{Array, Function, Object, Promise, Reflect, Uint8Array};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    window, Blob, BlobPropertyBag, DedicatedWorkerGlobalScope, MessageEvent, Url, Worker,
+    WorkerOptions, WorkerType,
+};
+
+use netthinne_core::image_utils::{self, ScanImages};
+use netthinne_core::ml::{self, DetectedObject, DetectionConfig, LabeledBox, Stage};
+
+use crate::error::{js_error, NetthinneError};
+use crate::history;
+
+/// Worker entry module. Its first message carries the glue URL and the compiled
+/// wasm module; a failure to load them is reported as `Response::StartFailed`.
+const BOOTSTRAP_JS: &str = r#"
+self.onmessage = async ({ data }) => {
+    self.onmessage = null;
+    try {
+        const glue = await import(data.glue);
+        await glue.default({ module_or_path: data.module });
+    } catch (e) {
+        self.postMessage(JSON.stringify({ type: "start_failed", error: String(e) }));
+    }
+};
+"#;
+/// How long a new worker may take to load the wasm bundle.
+const START_TIMEOUT_MS: i32 = 15_000;
+/// How long loading both models may take once the worker runs; the app then
+/// loads them on the main thread instead of waiting.
+const WARM_UP_TIMEOUT_MS: i32 = 30_000;
+/// How long a single request may run before the worker is considered stuck.
+/// Generous, since a full scan on a slow phone takes several seconds.
+const CALL_TIMEOUT_MS: i32 = 60_000;
+/// Rejection value of a timed-out promise, told apart from worker errors.
+const TIMED_OUT: &str = "timed out";
+
+#[wasm_bindgen]
+extern "C" {
+    /// URL of the wasm-bindgen glue module this code was loaded from.
+    #[wasm_bindgen(thread_local_v2, js_namespace = ["import", "meta"], js_name = url)]
+    static GLUE_URL: String;
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request {
+    WarmUp {
+        id: u32,
+    },
+    Process {
+        id: u32,
+        width: u32,
+        height: u32,
+        config: DetectionConfig,
+    },
+    Detect {
+        id: u32,
+        width: u32,
+        height: u32,
+        config: DetectionConfig,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Response {
+    /// The worker's wasm instance is running and accepts requests.
+    Started,
+    /// The worker could not load the wasm bundle.
+    StartFailed {
+        error: String,
+    },
+    Ready {
+        id: u32,
+    },
+    Progress {
+        id: u32,
+        stage: Stage,
+    },
+    Done {
+        id: u32,
+        detections: Vec<DetectedObject>,
+        images: ScanImages,
+    },
+    Detected {
+        id: u32,
+        boxes: Vec<LabeledBox>,
+    },
+    Failed {
+        id: u32,
+        error: NetthinneError,
+    },
+}
+
+impl Request {
+    fn id(&self) -> u32 {
+        match self {
+            Request::WarmUp { id } | Request::Process { id, .. } | Request::Detect { id, .. } => {
+                *id
+            }
+        }
+    }
+}
+
+impl Response {
+    /// The request this answers; `None` for the start-up messages.
+    fn id(&self) -> Option<u32> {
+        match self {
+            Response::Started | Response::StartFailed { .. } => None,
+            Response::Ready { id }
+            | Response::Progress { id, .. }
+            | Response::Done { id, .. }
+            | Response::Detected { id, .. }
+            | Response::Failed { id, .. } => Some(*id),
+        }
+    }
+}
+
+/// A request awaiting its final response from the worker.
+struct Pending {
+    resolve: Function,
+    reject: Function,
+    on_progress: Box<dyn FnMut(Stage)>,
+}
+
+thread_local! {
+    /// The worker and a promise that resolves once it has started.
+    static WORKER: RefCell<Option<(Worker, Promise)>> = const { RefCell::new(None) };
+    /// Settles the start promise of the worker being started.
+    static STARTING: RefCell<Option<(Function, Function)>> = const { RefCell::new(None) };
+    static PENDING: RefCell<HashMap<u32, Pending>> = RefCell::new(HashMap::new());
+    static NEXT_ID: Cell<u32> = const { Cell::new(0) };
+}
+
+/// True when this wasm instance is running inside a worker rather than a page.
+pub fn is_worker_scope() -> bool {
+    window().is_none()
+}
+
+/// Worker entry point: answers `Request`s posted by the page.
+pub fn run() {
+    let scope: DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    let reply_scope = scope.clone();
+
+    let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+        handle_request(&reply_scope, &event.data());
+    });
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    on_message.forget();
+    post(&scope, &Response::Started);
+}
+
+fn handle_request(scope: &DedicatedWorkerGlobalScope, data: &JsValue) {
+    let request = Reflect::get(data, &"request".into())
+        .ok()
+        .and_then(|v| v.as_string())
+        .and_then(|json| serde_json::from_str::<Request>(&json).ok());
+
+    match request {
+        Some(Request::WarmUp { id }) => {
+            ml::warm_up();
+            post(scope, &Response::Ready { id });
+        }
+        Some(Request::Process {
+            id,
+            width,
+            height,
+            config,
+        }) => {
+            let Some(pixels) = request_pixels(scope, id, data) else {
+                return;
+            };
+            let result = process_scan(&pixels, width, height, &config, |stage| {
+                post(scope, &Response::Progress { id, stage });
+            });
+            match result {
+                Ok((detections, images)) => post(
+                    scope,
+                    &Response::Done {
+                        id,
+                        detections,
+                        images,
+                    },
+                ),
+                Err(error) => post(
+                    scope,
+                    &Response::Failed {
+                        id,
+                        error: error.into(),
+                    },
+                ),
+            }
+        }
+        Some(Request::Detect {
+            id,
+            width,
+            height,
+            config,
+        }) => {
+            let Some(pixels) = request_pixels(scope, id, data) else {
+                return;
+            };
+            match ml::detect_objects(&pixels, width, height, &config) {
+                Ok(boxes) => post(scope, &Response::Detected { id, boxes }),
+                Err(error) => post(
+                    scope,
+                    &Response::Failed {
+                        id,
+                        error: error.into(),
+                    },
+                ),
+            }
+        }
+        None => log::error!("Worker received a malformed request"),
+    }
+}
+
+/// Pixel data sent with request `id`; replies `Failed` when it is missing.
+fn request_pixels(scope: &DedicatedWorkerGlobalScope, id: u32, data: &JsValue) -> Option<Vec<u8>> {
+    match Reflect::get(data, &"pixels".into()) {
+        Ok(v) if v.is_instance_of::<Uint8Array>() => {
+            Some(v.unchecked_into::<Uint8Array>().to_vec())
+        }
+        _ => {
+            post(
+                scope,
+                &Response::Failed {
+                    id,
+                    error: NetthinneError::Worker("request has no pixel data".into()),
+                },
+            );
+            None
+        }
+    }
+}
+
+fn post(scope: &DedicatedWorkerGlobalScope, response: &Response) {
+    let json = serde_json::to_string(response).unwrap_or_default();
+    if let Err(e) = scope.post_message(&JsValue::from_str(&json)) {
+        log::error!("Worker postMessage failed: {:?}", e);
+    }
+}
+
+/// Runs the full pipeline on this thread and encodes the scan's pictures.
+/// The worker answers `Request::Process` with it; the page calls it directly
+/// only when the worker is unavailable.
+pub fn process_scan(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    config: &DetectionConfig,
+    on_progress: impl FnMut(Stage),
+) -> Result<(Vec<DetectedObject>, ScanImages), netthinne_core::Error> {
+    let detections = ml::process_image(pixels, width, height, config, on_progress)?;
+    let bboxes: Vec<[f32; 4]> = detections.iter().map(|d| d.bbox).collect();
+    let images = image_utils::encode_scan_images(
+        pixels,
+        width,
+        height,
+        &bboxes,
+        history::THUMBNAIL_SIDE,
+        history::CROP_THUMBNAIL_SIDE,
+    )?;
+    Ok((detections, images))
+}
+
+/// Loads both models in the inference worker, resolving once they are ready.
+pub async fn warm_up() -> Result<(), NetthinneError> {
+    let request = Request::WarmUp { id: next_id() };
+    match call(request, None, WARM_UP_TIMEOUT_MS, |_| {}).await? {
+        Response::Ready { .. } => Ok(()),
+        other => Err(NetthinneError::Worker(format!(
+            "unexpected response: {:?}",
+            other
+        ))),
+    }
+}
+
+/// Runs [`process_scan`] in the inference worker, forwarding progress.
+/// The worker is started on first use and kept alive so models stay loaded.
+pub async fn process_image(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    config: &DetectionConfig,
+    on_progress: impl FnMut(Stage) + 'static,
+) -> Result<(Vec<DetectedObject>, ScanImages), NetthinneError> {
+    let request = Request::Process {
+        id: next_id(),
+        width,
+        height,
+        config: *config,
+    };
+    match call(request, Some(pixels), CALL_TIMEOUT_MS, on_progress).await? {
+        Response::Done {
+            detections, images, ..
+        } => Ok((detections, images)),
+        other => Err(NetthinneError::Worker(format!(
+            "unexpected response: {:?}",
+            other
+        ))),
+    }
+}
+
+/// Runs only the YOLO stage in the inference worker, for live preview frames.
+pub async fn detect_objects(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    config: &DetectionConfig,
+) -> Result<Vec<LabeledBox>, NetthinneError> {
+    let request = Request::Detect {
+        id: next_id(),
+        width,
+        height,
+        config: *config,
+    };
+    match call(request, Some(pixels), CALL_TIMEOUT_MS, |_| {}).await? {
+        Response::Detected { boxes, .. } => Ok(boxes),
+        other => Err(NetthinneError::Worker(format!(
+            "unexpected response: {:?}",
+            other
+        ))),
+    }
+}
+
+/// Posts `request` to the worker and waits up to `timeout_ms` for its final response.
+/// Progress responses are forwarded to `on_progress`; `Failed` becomes an `Err`.
+async fn call(
+    request: Request,
+    pixels: Option<&[u8]>,
+    timeout_ms: i32,
+    on_progress: impl FnMut(Stage) + 'static,
+) -> Result<Response, NetthinneError> {
+    let worker = inference_worker().await?;
+    let id = request.id();
+
+    let message = Object::new();
+    Reflect::set(
+        &message,
+        &"request".into(),
+        &serde_json::to_string(&request)
+            .map_err(|e| NetthinneError::Worker(format!("failed to encode request: {e}")))?
+            .into(),
+    )
+    .map_err(|e| NetthinneError::Worker(format!("failed to build message: {}", js_error(&e))))?;
+    let transfer = Array::new();
+    if let Some(pixels) = pixels {
+        let pixel_array = Uint8Array::from(pixels);
+        Reflect::set(&message, &"pixels".into(), &pixel_array).map_err(|e| {
+            NetthinneError::Worker(format!("failed to build message: {}", js_error(&e)))
+        })?;
+        transfer.push(&pixel_array.buffer());
+    }
+
+    let mut on_progress: Option<Box<dyn FnMut(Stage)>> = Some(Box::new(on_progress));
+    let promise = Promise::new(&mut |resolve, reject| {
+        if let Some(on_progress) = on_progress.take() {
+            PENDING.with(|pending| {
+                pending.borrow_mut().insert(
+                    id,
+                    Pending {
+                        resolve,
+                        reject,
+                        on_progress,
+                    },
+                )
+            });
+        }
+    });
+
+    if let Err(e) = worker.post_message_with_transfer(&message, &transfer) {
+        PENDING.with(|pending| pending.borrow_mut().remove(&id));
+        return Err(NetthinneError::Worker(format!(
+            "postMessage failed: {}",
+            js_error(&e)
+        )));
+    }
+
+    let json = JsFuture::from(with_timeout(&promise, timeout_ms))
+        .await
+        .map_err(|e| {
+            if e.as_string().as_deref() == Some(TIMED_OUT) {
+                // Whatever the worker is stuck on, the next call gets a fresh one.
+                fail_all(e.clone());
+            }
+            NetthinneError::Worker(js_error(&e))
+        })?
+        .as_string()
+        .ok_or_else(|| NetthinneError::Worker("resolved with a non-string".into()))?;
+    match serde_json::from_str::<Response>(&json) {
+        Ok(Response::Failed { error, .. }) => Err(error),
+        Ok(response) => Ok(response),
+        Err(e) => Err(NetthinneError::Worker(format!("malformed response: {e}"))),
+    }
+}
+
+/// Routes a worker message to the request it answers.
+fn dispatch(event: MessageEvent) {
+    let Some(json) = event.data().as_string() else {
+        return;
+    };
+    let response = match serde_json::from_str::<Response>(&json) {
+        Ok(response) => response,
+        Err(e) => {
+            log::error!("Malformed worker response: {e}");
+            return;
+        }
+    };
+
+    let id = match response {
+        Response::Started => return settle_start(Ok(())),
+        Response::StartFailed { error } => return settle_start(Err(error)),
+        _ => response.id().unwrap_or_default(),
+    };
+    let Some(mut pending) = PENDING.with(|pending| pending.borrow_mut().remove(&id)) else {
+        return;
+    };
+    match response {
+        Response::Progress { stage, .. } => {
+            (pending.on_progress)(stage);
+            PENDING.with(|p| p.borrow_mut().insert(id, pending));
+        }
+        _ => {
+            let _ = pending
+                .resolve
+                .call1(&JsValue::NULL, &JsValue::from_str(&json));
+        }
+    }
+}
+
+/// Resolves or rejects the start promise of the worker being started.
+fn settle_start(result: Result<(), String>) {
+    let Some((resolve, reject)) = STARTING.with(|cell| cell.borrow_mut().take()) else {
+        return;
+    };
+    let _ = match result {
+        Ok(()) => resolve.call0(&JsValue::NULL),
+        Err(error) => reject.call1(&JsValue::NULL, &JsValue::from_str(&error)),
+    };
+}
+
+/// Fails every in-flight request and drops the worker; the next call starts a new one.
+fn fail_all(error: JsValue) {
+    if let Some((worker, _)) = WORKER.with(|cell| cell.borrow_mut().take()) {
+        worker.terminate();
+    }
+    if let Some((_, reject)) = STARTING.with(|cell| cell.borrow_mut().take()) {
+        let _ = reject.call1(&JsValue::NULL, &error);
+    }
+    let pending: Vec<Pending> =
+        PENDING.with(|pending| pending.borrow_mut().drain().map(|(_, p)| p).collect());
+    for p in pending {
+        let _ = p.reject.call1(&JsValue::NULL, &error);
+    }
+}
+
+fn next_id() -> u32 {
+    NEXT_ID.with(|next| {
+        let id = next.get();
+        next.set(id.wrapping_add(1));
+        id
+    })
+}
+
+/// The running worker, started on first use. Fails if it does not start in time.
+async fn inference_worker() -> Result<Worker, NetthinneError> {
+    let (worker, started) = WORKER.with(|cell| {
+        if let Some(entry) = cell.borrow().as_ref() {
+            return Ok(entry.clone());
+        }
+        let entry = start_worker()?;
+        *cell.borrow_mut() = Some(entry.clone());
+        Ok::<_, NetthinneError>(entry)
+    })?;
+
+    if let Err(e) = JsFuture::from(with_timeout(&started, START_TIMEOUT_MS)).await {
+        fail_all(e.clone());
+        return Err(NetthinneError::Worker(format!(
+            "failed to start: {}",
+            js_error(&e)
+        )));
+    }
+    Ok(worker)
+}
+
+/// Spawns the bootstrap worker and sends it the bundle to instantiate.
+/// Returns the worker with a promise that settles on `Started` or `StartFailed`.
+fn start_worker() -> Result<(Worker, Promise), NetthinneError> {
+    let failed = |e: JsValue| NetthinneError::Worker(format!("failed to start: {}", js_error(&e)));
+
+    let options = BlobPropertyBag::new();
+    options.set_type("text/javascript");
+    let script = Blob::new_with_str_sequence_and_options(
+        &Array::of1(&JsValue::from_str(BOOTSTRAP_JS)),
+        &options,
+    )
+    .map_err(failed)?;
+    let script_url = Url::create_object_url_with_blob(&script).map_err(failed)?;
+
+    let options = WorkerOptions::new();
+    options.set_type(WorkerType::Module);
+    // The script URL is not revoked: the worker fetches it asynchronously, and
+    // one small blob per started worker is not worth the race.
+    let worker = Worker::new_with_options(&script_url, &options).map_err(failed)?;
+
+    // The handlers live as long as the worker; they are leaked rather than
+    // dropped from inside `onerror`, which would free a running closure.
+    let on_message = Closure::<dyn FnMut(MessageEvent)>::new(dispatch);
+    let on_error = Closure::<dyn FnMut(JsValue)>::new(fail_all);
+    worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    worker.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+    on_message.forget();
+    on_error.forget();
+
+    let started = Promise::new(&mut |resolve, reject| {
+        STARTING.with(|cell| *cell.borrow_mut() = Some((resolve, reject)));
+    });
+
+    let message = Object::new();
+    let glue = GLUE_URL.with(String::clone);
+    Reflect::set(&message, &"glue".into(), &glue.into()).map_err(failed)?;
+    Reflect::set(&message, &"module".into(), &wasm_bindgen::module()).map_err(failed)?;
+    worker.post_message(&message).map_err(failed)?;
+
+    Ok((worker, started))
+}
+
+/// `promise`, but rejected with `TIMED_OUT` if it has not settled after `ms`.
+fn with_timeout(promise: &Promise, ms: i32) -> Promise {
+    let timeout = Promise::new(&mut |_, reject| {
+        if let Some(window) = window() {
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_1(
+                &reject,
+                ms,
+                &JsValue::from_str(TIMED_OUT),
+            );
+        }
+    });
+    Promise::race(&Array::of2(promise, &timeout))
+}